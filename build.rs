@@ -1,4 +1,6 @@
 fn main() {
-    println!("cargo:rustc-link-lib=X11");
-    println!("cargo:rustc-link-lib=Xinerama");
+    // Only window manager talks to X server, `rtwmc` is linked without X libraries
+    for lib in ["X11", "Xinerama", "Xrandr"] {
+        println!("cargo:rustc-link-arg-bin=rtwm=-l{lib}");
+    }
 }
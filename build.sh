@@ -2,6 +2,8 @@
 
 if ! test -f src/config.rs; then
   cp src/config-example.rs src/config.rs
+elif test src/config-example.rs -nt src/config.rs; then
+  echo "src/config-example.rs changed since src/config.rs was created, merge new items if build fails"
 fi
 
 cargo install --path .
@@ -1,3 +1,5 @@
+use x11::xlib::AnyKey;
+use x11::xlib::AnyModifier;
 use x11::xlib::DestroyAll;
 use x11::xlib::PropModeReplace;
 use x11::xlib::XA_CARDINAL;
@@ -5,11 +7,129 @@ use x11::xlib::XA_CARDINAL;
 use crate::arrange::*;
 use crate::config;
 use crate::helper::*;
+use crate::ipc::emit_event;
+use crate::loader::*;
 use crate::logic::*;
+use crate::script::run_script;
+use crate::setup::init_actions;
+use crate::state::dump_state;
 use crate::structs::*;
 use crate::utils::*;
 use crate::wrapper::xlib::*;
 
+/// Bounds of client weight, so no client is squeezed out of its column
+pub const MIN_CLIENT_WEIGHT: f64 = 0.25;
+pub const MAX_CLIENT_WEIGHT: f64 = 4.0;
+
+/// Match action result and run related function
+pub fn run_action(app: &mut Application, result: &ActionResult) {
+    match result {
+        ActionResult::KillClient => {
+            kill_client(app);
+        }
+        ActionResult::Spawn(cmd) => {
+            spawn(app, &cmd.clone(), None);
+        }
+        ActionResult::MoveToScreen(d) => {
+            move_to_screen(app, *d);
+        }
+        ActionResult::FocusOnScreen(d) => {
+            focus_on_screen(app, *d);
+        }
+        ActionResult::MoveToWorkspace(n) => {
+            move_to_workspace(app, *n);
+        }
+        ActionResult::FocusOnWorkspace(n) => {
+            focus_on_workspace(app, *n, true);
+        }
+        ActionResult::Quit => {
+            app.core.running = false;
+        }
+        ActionResult::ReloadConfig => {
+            reload_config(app);
+        }
+        ActionResult::Script(name) => {
+            if let Err(e) = run_script(app, name) {
+                eprintln!("Script failed: {e}");
+            }
+        }
+        ActionResult::UpdateMasterCapacity(i) => {
+            update_master_capacity(app, *i);
+        }
+        ActionResult::UpdateMasterWidth(w) => {
+            update_master_width(app, *w);
+        }
+        ActionResult::UpdateClientWeight(w) => {
+            update_client_weight(app, *w);
+        }
+        ActionResult::DumpInfo => {
+            dump_state(app);
+        }
+        ActionResult::ToggleFloat => {
+            toggle_float(app);
+        }
+        ActionResult::CycleStack(d) => {
+            cycle_stack(app, *d);
+        }
+        ActionResult::PopPushStack => {
+            pop_push_stack(app, true);
+        }
+        ActionResult::RotateStack => {
+            pop_push_stack(app, false);
+        }
+        ActionResult::SetArrangeEngine(ae) => {
+            set_arrange_engine(app, ae.clone());
+        }
+        ActionResult::NextLayout => {
+            cycle_layout(app, 1);
+        }
+        ActionResult::PrevLayout => {
+            cycle_layout(app, -1);
+        }
+    }
+}
+
+pub fn set_arrange_engine(app: &mut Application, ae: ArrangeEngine) {
+    app.runtime.screens[app.runtime.current_screen].workspaces[app.runtime.current_workspace]
+        .arrange = ae.clone();
+    emit_event(
+        app,
+        WmEvent::LayoutChanged {
+            layout: ae,
+            screen: app.runtime.current_screen,
+            workspace: app.runtime.current_workspace,
+        },
+    );
+    arrange_workspace(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
+    show_workspace(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
+}
+
+/// Switch current workspace to next (`d` > 0) or previous layout of `layout_cycle`.
+/// Layout that is not in cycle is switched to first one
+pub fn cycle_layout(app: &mut Application, d: i64) {
+    let cycle = &app.config.layout_cycle;
+    if cycle.is_empty() {
+        return;
+    }
+    let current = app.runtime.screens[app.runtime.current_screen].workspaces
+        [app.runtime.current_workspace]
+        .arrange
+        .name();
+    let index = match cycle.iter().position(|ae| ae.name() == current) {
+        Some(i) => (i as i64 + d.signum()).rem_euclid(cycle.len() as i64) as usize,
+        None => 0,
+    };
+    set_arrange_engine(app, cycle[index].clone());
+}
+
 pub fn kill_client(app: &mut Application) {
     if let Some(index) = app.runtime.current_client {
         let id = app.runtime.screens[app.runtime.current_screen].workspaces
@@ -112,6 +232,13 @@ pub fn focus_on_screen(app: &mut Application, d: ScreenSwitching) {
         }
     };
     focus_on_screen_index(app, cs);
+    emit_event(
+        app,
+        WmEvent::WorkspaceFocused {
+            screen: cs,
+            workspace: app.runtime.current_workspace,
+        },
+    );
 }
 
 pub fn move_client_to_workspace(app: &mut Application, mut client: Client, n: u64) {
@@ -189,7 +316,15 @@ pub fn cycle_stack(app: &mut Application, d: i64) {
             None => return,
         }
     };
-    let new_index = (((num_clients + cur_index) as i64 + d) % num_clients as i64) as usize;
+    // Skip windows that can't take focus
+    let step = |k: i64| ((cur_index as i64 + d * k).rem_euclid(num_clients as i64)) as usize;
+    let new_index = match (1..num_clients as i64)
+        .map(step)
+        .find(|&i| !ws.clients[i].hidden && !ws.clients[i].never_focus)
+    {
+        Some(i) => i,
+        None => return,
+    };
 
     let old_win = ws.clients[cur_index].window_id;
     let new_win = ws.clients[new_index].window_id;
@@ -202,15 +337,14 @@ pub fn cycle_stack(app: &mut Application, d: i64) {
 }
 
 pub fn pop_push_stack(app: &mut Application, current: bool) {
-    let workspace = &mut app.runtime.screens[app.runtime.current_screen].workspaces
-        [app.runtime.current_workspace];
-
-    match workspace.arrange {
-        ArrangeEngine::Tiled => {}
+    let (screen, workspace) = (app.runtime.current_screen, app.runtime.current_workspace);
+    match workspace_layout(app, screen, workspace) {
+        Some(layout) if layout.reorderable() => {}
         _ => {
             return;
         }
     }
+    let workspace = &mut app.runtime.screens[screen].workspaces[workspace];
 
     // No need to rotate single window;
     if workspace.clients.len() < 2 {
@@ -259,6 +393,7 @@ pub fn pop_push_stack(app: &mut Application, current: bool) {
 }
 
 pub fn focus_on_workspace(app: &mut Application, n: u64, r: bool) {
+    let previous = (app.runtime.current_screen, app.runtime.current_workspace);
     let n = if !r {
         focus_on_screen_index(app, n as usize / config::NUMBER_OF_DESKTOPS);
         n % config::NUMBER_OF_DESKTOPS as u64
@@ -277,6 +412,11 @@ pub fn focus_on_workspace(app: &mut Application, n: u64, r: bool) {
         app.runtime.current_workspace = n as usize;
         app.runtime.screens[app.runtime.current_screen].current_workspace = n as usize;
 
+        // Sticky clients follow
+        move_sticky_clients(app, app.runtime.current_screen, pw, n as usize);
+        arrange_workspace(app, app.runtime.current_screen, pw);
+        arrange_workspace(app, app.runtime.current_screen, n as usize);
+
         let w = n + app.runtime.current_screen as u64 * config::NUMBER_OF_DESKTOPS as u64;
 
         change_property(
@@ -308,19 +448,28 @@ pub fn focus_on_workspace(app: &mut Application, n: u64, r: bool) {
         hide_workspace(app, app.runtime.current_screen, pw);
     }
 
+    if previous != (app.runtime.current_screen, app.runtime.current_workspace) {
+        emit_event(
+            app,
+            WmEvent::WorkspaceFocused {
+                screen: app.runtime.current_screen,
+                workspace: app.runtime.current_workspace,
+            },
+        );
+    }
+
     suppress_notify(app);
 }
 
 pub fn update_master_width(app: &mut Application, w: f64) {
-    let workspace = &mut app.runtime.screens[app.runtime.current_screen].workspaces
-        [app.runtime.current_workspace];
-
-    match workspace.arrange {
-        ArrangeEngine::Tiled => {}
+    let (screen, workspace) = (app.runtime.current_screen, app.runtime.current_workspace);
+    match workspace_layout(app, screen, workspace) {
+        Some(layout) if layout.uses_master_width() => {}
         _ => {
             return;
         }
     }
+    let workspace = &mut app.runtime.screens[screen].workspaces[workspace];
 
     // Update master width
     let mw = &mut workspace.master_width;
@@ -337,16 +486,55 @@ pub fn update_master_width(app: &mut Application, w: f64) {
     suppress_notify(app);
 }
 
-pub fn update_master_capacity(app: &mut Application, i: i64) {
-    let workspace = &mut app.runtime.screens[app.runtime.current_screen].workspaces
-        [app.runtime.current_workspace];
+/// Weight changed by `delta`, `0` resets it.
+/// Weight is kept between [`MIN_CLIENT_WEIGHT`] and [`MAX_CLIENT_WEIGHT`]
+pub fn changed_weight(weight: f64, delta: f64) -> f64 {
+    if delta == 0.0 {
+        1.0
+    } else {
+        (weight + delta).clamp(MIN_CLIENT_WEIGHT, MAX_CLIENT_WEIGHT)
+    }
+}
 
-    match workspace.arrange {
-        ArrangeEngine::Tiled => {}
+/// Change weight of current client, see [`changed_weight`]
+pub fn update_client_weight(app: &mut Application, w: f64) {
+    let (screen, workspace) = (app.runtime.current_screen, app.runtime.current_workspace);
+    match workspace_layout(app, screen, workspace) {
+        Some(layout) if layout.uses_client_weight() => {}
         _ => {
             return;
         }
     }
+    let workspace = &mut app.runtime.screens[screen].workspaces[workspace];
+    let client = match workspace.current_client {
+        Some(index) => &mut workspace.clients[index],
+        None => return,
+    };
+    if client.floating {
+        return;
+    }
+
+    // Update weight
+    client.weight = changed_weight(client.weight, w);
+    // Rearrange windows
+    arrange_visible(app);
+    show_workspace(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
+    suppress_notify(app);
+}
+
+pub fn update_master_capacity(app: &mut Application, i: i64) {
+    let (screen, workspace) = (app.runtime.current_screen, app.runtime.current_workspace);
+    match workspace_layout(app, screen, workspace) {
+        Some(layout) if layout.uses_master_capacity() => {}
+        _ => {
+            return;
+        }
+    }
+    let workspace = &mut app.runtime.screens[screen].workspaces[workspace];
     // Change master size
     workspace.master_capacity += i;
     // Rearrange windows
@@ -367,7 +555,7 @@ pub fn toggle_float(app: &mut Application) {
         client.floating = !client.floating;
 
         client.border = if client.floating {
-            app.config.border_size as u32
+            floating_border(&app.config, client)
         } else {
             0
         };
@@ -425,3 +613,82 @@ pub fn focus_on_screen_index(app: &mut Application, n: usize) {
         1,
     );
 }
+
+/// Re-read configuration and apply it to running WM
+///
+/// 1. Load config, keep current one if config file is broken
+/// 2. Regrab keys
+/// 3. Update borders of all clients
+/// 4. Rearrange with new gaps & borders
+/// 5. Update desktop names
+pub fn reload_config(app: &mut Application) {
+    log!("   |- Got `ReloadConfig` Action");
+    // 1. Load
+    let tab_font = app.config.tab_bar.font.clone();
+    app.config = match config_path() {
+        Some(path) if path.exists() => match read_config_file(&path) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("{e}");
+                eprintln!("Keeping current configuration");
+                return;
+            }
+        },
+        _ => config::config(),
+    };
+    if app.config.tab_bar.font != tab_font {
+        // Loaded again on next draw
+        if let Some(font) = app.runtime.tab_font.take() {
+            free_font_set(app.core.display, font);
+        }
+    }
+
+    // 2. Regrab keys
+    ungrab_key(app.core.display, AnyKey, AnyModifier);
+    init_actions(app);
+
+    // 3. Update borders
+    let current = get_current_client_id(app);
+    for screen in &mut app.runtime.screens {
+        for workspace in &mut screen.workspaces {
+            for client in &mut workspace.clients {
+                let color = if client.urgent {
+                    app.config.urgent_border_color
+                } else if Some(client.window_id) == current {
+                    app.config.active_border_color
+                } else {
+                    app.config.normal_border_color
+                };
+                set_window_border(app.core.display, client.window_id, argb_to_int(color));
+                if client.floating && !client.fullscreen {
+                    client.border = floating_border(&app.config, client);
+                }
+            }
+        }
+    }
+
+    // 4. Rearrange
+    arrange_all(app);
+    for screen in 0..app.runtime.screens.len() {
+        show_workspace(app, screen, app.runtime.screens[screen].current_workspace);
+    }
+
+    // 5. Update names
+    update_desktops(app);
+    suppress_notify(app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_weight_is_clamped() {
+        assert_eq!(changed_weight(1.0, 0.5), 1.5);
+        assert_eq!(changed_weight(3.5, 1.0), MAX_CLIENT_WEIGHT);
+        assert_eq!(changed_weight(0.5, -1.0), MIN_CLIENT_WEIGHT);
+        assert_eq!(changed_weight(MIN_CLIENT_WEIGHT, -0.25), MIN_CLIENT_WEIGHT);
+        // Zero resets
+        assert_eq!(changed_weight(3.0, 0.0), 1.0);
+    }
+}
@@ -1,120 +1,660 @@
+use crate::layout::*;
 use crate::structs::*;
 use crate::utils::*;
 
-/// Arrange windows of specified workspace in specified layout
-/// 1. Get structs by index
-/// 2. Calculate usable screen sizes, gaps, borders etc
-/// 3. Get amount of clients to be tiled
-/// 4. Check if all client go to master
-/// 5. Iterate all clients in current workspace and calculate geometry
-/// 6. Show maximized clients
-/// 7. Show master clients
-/// 8. Show stack clients
-/// 9. Update calculated geometry
-/// 10. Fullscreen window if needed
-/// 11. Update borders
-/// 12. Position windows
+/// Arrange windows of specified workspace in its layout
+/// 1. Get clients to be tiled, newest first
+/// 2. Calculate usable screen area
+/// 3. Stretch maximized clients over area
+/// 4. Pick layout, single client is always shown in mono
+/// 5. Calculate geometry
+/// 6. Update clients
 pub fn arrange_workspace(app: &mut Application, screen: usize, workspace: usize) {
-    let arrange_engine = {
-        let screen = &mut app.runtime.screens[screen];
-        let workspace = &mut screen.workspaces[workspace];
+    let runtime = &mut app.runtime;
+    let screen = &mut runtime.screens[screen];
+    let workspace = &mut screen.workspaces[workspace];
+
+    // 1. Get clients
+    let stack_size = workspace.clients.iter().filter(|c| is_tiled(c)).count();
+    let indexes: Vec<usize> = (0..workspace.clients.len())
+        .rev()
+        .filter(|&i| is_tiled(&workspace.clients[i]) && !workspace.clients[i].fullscreen)
+        .collect();
 
-        let stack_size = workspace.clients.iter().filter(|&c| !c.floating).count();
+    // 2. Calculate usable area
+    let bar_offsets = screen.bar_offsets;
+    let area = Area {
+        x: screen.x as i32 + bar_offsets.left as i32,
+        y: screen.y as i32 + bar_offsets.up as i32,
+        w: screen.width as i32 - (bar_offsets.left + bar_offsets.right) as i32,
+        h: screen.height as i32 - (bar_offsets.up + bar_offsets.down) as i32,
+    };
 
-        match workspace.arrange {
-            _ if stack_size == 0 => return,
-            _ if stack_size == 1 => ArrangeEngine::Mono,
-            ArrangeEngine::Tiled => ArrangeEngine::Tiled,
-            ArrangeEngine::Mono => ArrangeEngine::Mono,
+    // 3. Maximized
+    for client in workspace.clients.iter_mut().filter(|c| !c.fullscreen) {
+        let border = 2 * client.border as i32;
+        if client.maximized_horz {
+            client.x = area.x;
+            client.w = (area.w - border).max(1) as u32;
+        }
+        if client.maximized_vert {
+            client.y = area.y;
+            client.h = (area.h - border).max(1) as u32;
+        }
+    }
+    if indexes.is_empty() {
+        return;
+    }
+
+    // 4. Pick layout
+    let name = if stack_size == 1 {
+        ArrangeEngine::Mono.name()
+    } else {
+        workspace.arrange.name()
+    };
+    let layout = match runtime.layouts.get(name) {
+        Some(layout) => layout,
+        None => {
+            eprintln!("Layout `{name}` is not registered, using tiled");
+            match runtime.layouts.get(ArrangeEngine::Tiled.name()) {
+                Some(layout) => layout,
+                None => return,
+            }
         }
     };
+    log!(
+        "   |- Arranging {} tilable window in {}",
+        indexes.len(),
+        layout.name()
+    );
 
-    match arrange_engine {
-        ArrangeEngine::Tiled => tiled(app, screen, workspace),
-        ArrangeEngine::Mono => mono(app, screen, workspace),
+    let settings = LayoutSettings {
+        gap: workspace.gap.unwrap_or(app.config.gap_width) as i32,
+        outer_gap: workspace.gap.unwrap_or(app.config.outer_gap_width) as i32,
+        smart_gaps: app.config.smart_gaps,
+        smart_borders: app.config.smart_borders,
+        border: app.config.border_size as u32,
+        master_width: workspace.master_width,
+        master_capacity: workspace.master_capacity,
+        three_column_master: app.config.three_column_master,
+        tab_bar_height: app.config.tab_bar.height,
     };
+
+    // 5. Calculate geometry
+    let clients: Vec<&Client> = indexes.iter().map(|&i| &workspace.clients[i]).collect();
+    let geometry = layout.arrange(area, &settings, &clients);
+
+    // 6. Update clients
+    for (&index, geometry) in indexes.iter().zip(geometry) {
+        if let Some(g) = geometry {
+            let client = &mut workspace.clients[index];
+            client.x = g.x;
+            client.y = g.y;
+            client.w = g.w;
+            client.h = g.h;
+            client.border = g.border;
+        }
+    }
 }
 
-pub fn tiled(app: &mut Application, screen: usize, workspace: usize) {
-    let screen = &mut app.runtime.screens[screen];
-    let workspace = &mut screen.workspaces[workspace];
-    // 2. Calculate usable screen sizes, gaps, borders etc
-    let bar_offsets = screen.bar_offsets;
-    let screen_height = screen.height - (bar_offsets.up + bar_offsets.down) as i64;
-    let gap = app.config.gap_width as i32;
-    let border = app.config.border_size as u32;
-    let mut master_width = ((screen.width as i32 - gap * 3) as f64 * workspace.master_width) as u32;
-    let stack_width = (screen.width as i32 - gap * 3) - master_width as i32;
-    let mut master_capacity = workspace.master_capacity;
-
-    // 3. Get amount of clients to be tiled
-    let stack_size = workspace.clients.iter().filter(|&c| !c.floating).count();
-    // 4. Check if all client go to master
-    if master_capacity <= 0 || master_capacity >= stack_size as i64 {
-        master_capacity = stack_size as i64;
-        master_width = screen.width as u32 - gap as u32 * 2;
-    }
-    log!("   |- Arranging {} tilable window", stack_size);
-    // 5. Iterate all clients in current workspace and calculate geometry
-    for (index, client) in workspace
-        .clients
-        .iter_mut()
-        .rev()
-        .filter(|c| !c.floating && !c.fullscreen)
+/// Client takes part in layout, fullscreen ones are counted but not placed
+pub fn is_tiled(client: &Client) -> bool {
+    !client.floating && !client.hidden && !client.maximized_vert && !client.maximized_horz
+}
+
+/// Layout of workspace, `None` if it is not registered
+pub fn workspace_layout(app: &Application, screen: usize, workspace: usize) -> Option<&dyn Layout> {
+    let name = app.runtime.screens[screen].workspaces[workspace]
+        .arrange
+        .name();
+    app.runtime.layouts.get(name)
+}
+
+/// Layouts registered on start
+pub fn builtin_layouts() -> Vec<Box<dyn Layout>> {
+    vec![
+        Box::new(Tiled),
+        Box::new(Mono),
+        Box::new(Grid),
+        Box::new(CenteredMaster),
+        Box::new(BottomStack),
+        Box::new(Fibonacci { spiral: true }),
+        Box::new(Fibonacci { spiral: false }),
+        Box::new(Tabbed),
+        Box::new(ThreeColumn),
+    ]
+}
+
+/// Area for helpers below, which put `gap` at area edges too.
+/// Shrunk so edges end up with `outer_gap` instead
+fn tiling_area(area: Area, settings: &LayoutSettings) -> Area {
+    area.shrink(settings.outer_gap - settings.gap)
+}
+
+/// Place client `index` of `count` clients stacked vertically in column
+fn in_column(column: Area, index: i32, count: i32, gap: i32, border: u32) -> Geometry {
+    let win_height = (column.h - gap - count * gap) / count;
+    let y = column.y + gap + (win_height + gap) * index;
+    let h = if index != count - 1 {
+        win_height
+    } else {
+        column.h - gap - y + column.y
+    };
+    Geometry::inside(Area { y, h, ..column }, border)
+}
+
+/// Stack clients vertically in column, heights proportional to their weights
+fn weighted_column(column: Area, clients: &[&Client], gap: i32, border: u32) -> Vec<Geometry> {
+    let count = clients.len() as i32;
+    let available = column.h - gap - count * gap;
+    let weight = |c: &Client| if c.weight > 0.0 { c.weight } else { 1.0 };
+    let total: f64 = clients.iter().map(|c| weight(c)).sum();
+    let mut y = column.y + gap;
+    clients
+        .iter()
         .enumerate()
-    {
-        if (index as i64) < master_capacity {
-            // 7. Show master clients
-            let win_height =
-                (screen_height - gap as i64 - master_capacity * gap as i64) / master_capacity;
-            client.x = gap;
-            client.y = bar_offsets.up as i32 + gap + (win_height as i32 + gap) * index as i32;
-            client.w = master_width - 2 * border;
-            client.h = if index as i64 != master_capacity - 1 {
-                win_height as u32 - 2 * border
+        .map(|(index, client)| {
+            // Last client takes what is left after rounding
+            let h = if index as i32 != count - 1 {
+                (available as f64 * weight(client) / total) as i32
             } else {
-                (screen_height as i32 - gap - client.y + bar_offsets.up as i32) as u32 - 2 * border
+                column.h - gap - y + column.y
             };
+            let geometry = Geometry::inside(Area { y, h, ..column }, border);
+            y += h + gap;
+            geometry
+        })
+        .collect()
+}
+
+/// Place client `index` of `count` clients placed side by side in row
+fn in_row(row: Area, index: i32, count: i32, gap: i32, border: u32) -> Geometry {
+    let win_width = (row.w - gap - count * gap) / count;
+    let x = row.x + gap + (win_width + gap) * index;
+    let w = if index != count - 1 {
+        win_width
+    } else {
+        row.w - gap - x + row.x
+    };
+    Geometry::inside(Area { x, w, ..row }, border)
+}
+
+/// Split area in two along width or height, first part gets `ratio` of space
+fn split_area(area: Area, vertical: bool, ratio: f64, gap: i32) -> (Area, Area) {
+    if vertical {
+        let w = ((area.w - gap) as f64 * ratio) as i32;
+        (
+            Area { w, ..area },
+            Area {
+                x: area.x + w + gap,
+                w: area.w - w - gap,
+                ..area
+            },
+        )
+    } else {
+        let h = ((area.h - gap) as f64 * ratio) as i32;
+        (
+            Area { h, ..area },
+            Area {
+                y: area.y + h + gap,
+                h: area.h - h - gap,
+                ..area
+            },
+        )
+    }
+}
+
+/// Amount of master clients, all clients if capacity is not positive or too big
+fn master_count(settings: &LayoutSettings, stack_size: i32) -> i32 {
+    if settings.master_capacity <= 0 || settings.master_capacity >= stack_size as i64 {
+        stack_size
+    } else {
+        settings.master_capacity as i32
+    }
+}
+
+/// Master clients in left column, others stacked in right column.
+/// Heights in column are split by client weights
+pub struct Tiled;
+
+impl Layout for Tiled {
+    fn name(&self) -> &str {
+        ArrangeEngine::Tiled.name()
+    }
+
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>> {
+        let gap = settings.gap;
+        let area = tiling_area(area, settings);
+        let stack_size = clients.len() as i32;
+        let master_capacity = master_count(settings, stack_size);
+        // Check if all client go to master
+        let (master_width, stack_width) = if master_capacity == stack_size {
+            (area.w - gap * 2, 0)
         } else {
-            // 8. Show stack clients
-            let win_height =
-                (screen_height - gap as i64 - (stack_size as i64 - master_capacity) * gap as i64)
-                    / (stack_size as i64 - master_capacity);
-            client.x = master_width as i32 + (gap * 2);
-            client.y = bar_offsets.up as i32
-                + gap
-                + (win_height as i32 + gap) * (index as i64 - master_capacity) as i32;
-            client.w = stack_width as u32 - 2 * border;
-            client.h = if index != stack_size - 1 {
-                win_height as u32 - 2 * border
-            } else {
-                (screen_height as i32 - gap - client.y + bar_offsets.up as i32) as u32 - 2 * border
-            };
+            let mw = ((area.w - gap * 3) as f64 * settings.master_width) as i32;
+            (mw, area.w - gap * 3 - mw)
+        };
+        let master = Area {
+            x: area.x + gap,
+            w: master_width,
+            ..area
+        };
+        let stack = Area {
+            x: area.x + master_width + gap * 2,
+            w: stack_width,
+            ..area
+        };
+
+        let (master_clients, stack_clients) = clients.split_at(master_capacity as usize);
+        let mut geometry = weighted_column(master, master_clients, gap, settings.border);
+        if !stack_clients.is_empty() {
+            geometry.extend(weighted_column(stack, stack_clients, gap, settings.border));
         }
-        client.border = app.config.border_size as u32;
+        geometry.into_iter().map(Some).collect()
+    }
+
+    fn uses_master_width(&self) -> bool {
+        true
+    }
 
-        client.x += screen.x as i32;
-        client.y += screen.y as i32;
+    fn uses_master_capacity(&self) -> bool {
+        true
+    }
+
+    fn uses_client_weight(&self) -> bool {
+        true
     }
 }
 
-pub fn mono(app: &mut Application, screen: usize, workspace: usize) {
-    let screen = &mut app.runtime.screens[screen];
-    let workspace = &mut screen.workspaces[workspace];
+/// Every client takes whole area, clients with fixed size are left as is.
+/// Gaps & borders are only shown if `smart_gaps` & `smart_borders` are off
+pub struct Mono;
 
-    let bar_offsets = screen.bar_offsets;
-    let screen_height = screen.height - (bar_offsets.up + bar_offsets.down) as i64;
+impl Layout for Mono {
+    fn name(&self) -> &str {
+        ArrangeEngine::Mono.name()
+    }
+
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>> {
+        let gap = if settings.smart_gaps {
+            0
+        } else {
+            settings.outer_gap
+        };
+        let border = if settings.smart_borders {
+            0
+        } else {
+            settings.border
+        };
+        let area = area.shrink(gap);
+        clients
+            .iter()
+            .map(|c| (!c.fixed).then(|| Geometry::inside(area, border)))
+            .collect()
+    }
+
+    fn reorderable(&self) -> bool {
+        false
+    }
+}
+
+/// [`Mono`] below strip of tabs, one per client. Strip is drawn by [`crate::tabs`]
+pub struct Tabbed;
+
+impl Layout for Tabbed {
+    fn name(&self) -> &str {
+        ArrangeEngine::Tabbed.name()
+    }
+
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>> {
+        let below_tabs = Area {
+            y: area.y + settings.tab_bar_height,
+            h: area.h - settings.tab_bar_height,
+            ..area
+        };
+        Mono.arrange(below_tabs, settings, clients)
+    }
+
+    fn reorderable(&self) -> bool {
+        false
+    }
+}
+
+/// Clients in near-square grid, filling rows first.
+/// Clients of last row are stretched to fill its width
+pub struct Grid;
+
+impl Layout for Grid {
+    fn name(&self) -> &str {
+        ArrangeEngine::Grid.name()
+    }
+
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>> {
+        let gap = settings.gap;
+        let area = tiling_area(area, settings);
+        // Get grid size
+        let stack_size = clients.len() as i32;
+        let columns = (1..).find(|c| c * c >= stack_size).unwrap_or(1);
+        let rows = (stack_size + columns - 1) / columns;
+        let row_height = (area.h - gap * (rows + 1)) / rows;
+
+        (0..stack_size)
+            .map(|index| {
+                let (row, column) = (index / columns, index % columns);
+                // Last row may be not full and takes what is left after rounding
+                let (row_size, h) = if row == rows - 1 {
+                    let y = gap + (row_height + gap) * row;
+                    (stack_size - columns * (rows - 1), area.h - y - gap)
+                } else {
+                    (columns, row_height)
+                };
+                let row_area = Area {
+                    y: area.y + gap + (row_height + gap) * row,
+                    h,
+                    ..area
+                };
+                Some(in_row(row_area, column, row_size, gap, settings.border))
+            })
+            .collect()
+    }
+}
+
+/// Master clients in the middle of screen, stack clients alternately
+/// in right and left columns. With single stack client master goes left
+pub struct CenteredMaster;
+
+impl Layout for CenteredMaster {
+    fn name(&self) -> &str {
+        ArrangeEngine::CenteredMaster.name()
+    }
+
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>> {
+        let gap = settings.gap;
+        let area = tiling_area(area, settings);
+        let border = settings.border;
+        let stack_size = clients.len() as i32;
+        let master_capacity = master_count(settings, stack_size);
+        let stack_clients = stack_size - master_capacity;
+
+        // Get columns
+        let column = |x: i32, w: i32| Area {
+            x: area.x + x,
+            w,
+            ..area
+        };
+        let (master, left, right) = match stack_clients {
+            0 => (column(gap, area.w - gap * 2), None, None),
+            1 => {
+                let mw = ((area.w - gap * 3) as f64 * settings.master_width) as i32;
+                let right = column(mw + gap * 2, area.w - gap * 3 - mw);
+                (column(gap, mw), None, Some(right))
+            }
+            _ => {
+                let mw = ((area.w - gap * 4) as f64 * settings.master_width) as i32;
+                let side = (area.w - gap * 4 - mw) / 2;
+                let right_x = side + mw + gap * 3;
+                (
+                    column(side + gap * 2, mw),
+                    Some(column(gap, side)),
+                    Some(column(right_x, area.w - gap - right_x)),
+                )
+            }
+        };
+        let right_count = (stack_clients + 1) / 2;
+        let left_count = stack_clients / 2;
+
+        (0..stack_size)
+            .map(|index| {
+                if index < master_capacity {
+                    return Some(in_column(master, index, master_capacity, gap, border));
+                }
+                let stack_index = index - master_capacity;
+                match (left, right) {
+                    (Some(left), _) if stack_index % 2 == 1 => {
+                        Some(in_column(left, stack_index / 2, left_count, gap, border))
+                    }
+                    (Some(_), Some(right)) => {
+                        Some(in_column(right, stack_index / 2, right_count, gap, border))
+                    }
+                    (None, Some(right)) => {
+                        Some(in_column(right, stack_index, stack_clients, gap, border))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn uses_master_width(&self) -> bool {
+        true
+    }
 
-    for client in workspace
-        .clients
-        .iter_mut()
-        .filter(|c| !c.floating && !c.fullscreen && !c.fixed)
-    {
-        client.x = screen.x as i32;
-        client.y = screen.y as i32 + bar_offsets.up as i32;
-        client.w = screen.width as u32;
-        client.h = screen_height as u32;
-        client.border = 0;
+    fn uses_master_capacity(&self) -> bool {
+        true
+    }
+}
+
+/// Master column with stack clients split between two columns, first half goes next to master.
+/// Master column is left or in the middle, see [`MasterPosition`].
+/// With single stack client it is [`Tiled`]
+pub struct ThreeColumn;
+
+impl Layout for ThreeColumn {
+    fn name(&self) -> &str {
+        ArrangeEngine::ThreeColumn.name()
+    }
+
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>> {
+        let gap = settings.gap;
+        let stack_size = clients.len() as i32;
+        let master_capacity = master_count(settings, stack_size);
+        if stack_size - master_capacity < 2 {
+            return Tiled.arrange(area, settings, clients);
+        }
+        let area = tiling_area(area, settings);
+
+        // Get columns
+        let column = |x: i32, w: i32| Area {
+            x: area.x + x,
+            w,
+            ..area
+        };
+        let mw = ((area.w - gap * 4) as f64 * settings.master_width) as i32;
+        let side = (area.w - gap * 4 - mw) / 2;
+        let (master, first, second) = match settings.three_column_master {
+            MasterPosition::Left => {
+                let second_x = mw + side + gap * 3;
+                (
+                    column(gap, mw),
+                    column(mw + gap * 2, side),
+                    column(second_x, area.w - gap - second_x),
+                )
+            }
+            MasterPosition::Center => {
+                let first_x = side + mw + gap * 3;
+                (
+                    column(side + gap * 2, mw),
+                    column(first_x, area.w - gap - first_x),
+                    column(gap, side),
+                )
+            }
+        };
+
+        let (master_clients, stack_clients) = clients.split_at(master_capacity as usize);
+        let (first_clients, second_clients) =
+            stack_clients.split_at(stack_clients.len().div_ceil(2));
+        let mut geometry = weighted_column(master, master_clients, gap, settings.border);
+        geometry.extend(weighted_column(first, first_clients, gap, settings.border));
+        geometry.extend(weighted_column(
+            second,
+            second_clients,
+            gap,
+            settings.border,
+        ));
+        geometry.into_iter().map(Some).collect()
+    }
+
+    fn uses_master_width(&self) -> bool {
+        true
+    }
+
+    fn uses_master_capacity(&self) -> bool {
+        true
+    }
+
+    fn uses_client_weight(&self) -> bool {
+        true
+    }
+}
+
+/// Horizontal [`Tiled`]: master clients side by side across the top,
+/// stack clients side by side along the bottom. `master_width` is used as master height
+pub struct BottomStack;
+
+impl Layout for BottomStack {
+    fn name(&self) -> &str {
+        ArrangeEngine::BottomStack.name()
+    }
+
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>> {
+        let gap = settings.gap;
+        let area = tiling_area(area, settings);
+        let stack_size = clients.len() as i32;
+        let master_capacity = master_count(settings, stack_size);
+        // Check if all client go to master
+        let master_height = if master_capacity == stack_size {
+            area.h - gap * 2
+        } else {
+            ((area.h - gap * 3) as f64 * settings.master_width) as i32
+        };
+        let master = Area {
+            y: area.y + gap,
+            h: master_height,
+            ..area
+        };
+        let stack = Area {
+            y: master.y + master_height + gap,
+            h: area.h - gap * 3 - master_height,
+            ..area
+        };
+
+        (0..stack_size)
+            .map(|index| {
+                Some(if index < master_capacity {
+                    in_row(master, index, master_capacity, gap, settings.border)
+                } else {
+                    let count = stack_size - master_capacity;
+                    in_row(stack, index - master_capacity, count, gap, settings.border)
+                })
+            })
+            .collect()
+    }
+
+    fn uses_master_width(&self) -> bool {
+        true
+    }
+
+    fn uses_master_capacity(&self) -> bool {
+        true
+    }
+}
+
+/// Every client takes part of remaining area, splits alternate between vertical and horizontal.
+/// First split uses `master_width`, others halve remaining area.
+/// With `spiral` clients go clockwise around the center, otherwise they dwindle to bottom right
+pub struct Fibonacci {
+    pub spiral: bool,
+}
+
+impl Layout for Fibonacci {
+    fn name(&self) -> &str {
+        if self.spiral {
+            ArrangeEngine::Spiral.name()
+        } else {
+            ArrangeEngine::Dwindle.name()
+        }
+    }
+
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>> {
+        let gap = settings.gap;
+        let area = tiling_area(area, settings);
+        let stack_size = clients.len();
+        let mut rest = Area {
+            x: area.x + gap,
+            y: area.y + gap,
+            w: area.w - gap * 2,
+            h: area.h - gap * 2,
+        };
+
+        (0..stack_size)
+            .map(|index| {
+                // Last client takes what is left
+                if index == stack_size - 1 {
+                    return Some(Geometry::inside(rest, settings.border));
+                }
+                let ratio = if index == 0 {
+                    settings.master_width
+                } else {
+                    0.5
+                };
+                let vertical = index % 2 == 0;
+                // Spiral takes second part every other split, so clients go around
+                let swap = self.spiral && index % 4 >= 2;
+                let ratio = if swap { 1.0 - ratio } else { ratio };
+                let (first, second) = split_area(rest, vertical, ratio, gap);
+                let (client_area, remaining) = if swap {
+                    (second, first)
+                } else {
+                    (first, second)
+                };
+                rest = remaining;
+                Some(Geometry::inside(client_area, settings.border))
+            })
+            .collect()
+    }
+
+    fn uses_master_width(&self) -> bool {
+        true
     }
 }
 
@@ -136,3 +676,309 @@ pub fn arrange_all(app: &mut Application) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Area = Area {
+        x: 100,
+        y: 50,
+        w: 1920,
+        h: 1080,
+    };
+
+    fn settings(three_column_master: MasterPosition) -> LayoutSettings {
+        LayoutSettings {
+            gap: 5,
+            outer_gap: 12,
+            smart_gaps: false,
+            smart_borders: false,
+            border: 2,
+            master_width: 0.55,
+            master_capacity: 1,
+            three_column_master,
+            tab_bar_height: 20,
+        }
+    }
+
+    /// Area covered by geometry, border included
+    fn footprint(g: &Geometry) -> Area {
+        Area {
+            x: g.x,
+            y: g.y,
+            w: g.w as i32 + 2 * g.border as i32,
+            h: g.h as i32 + 2 * g.border as i32,
+        }
+    }
+
+    fn contains(outer: Area, inner: Area) -> bool {
+        inner.x >= outer.x
+            && inner.y >= outer.y
+            && inner.x + inner.w <= outer.x + outer.w
+            && inner.y + inner.h <= outer.y + outer.h
+    }
+
+    fn overlaps(a: Area, b: Area) -> bool {
+        a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+    }
+
+    /// Arranges 1..=5 clients & checks every tile stays within outer gap, and tiles of
+    /// layouts other than `Mono` & `Tabbed` don't overlap
+    fn check(layout: &dyn Layout, settings: &LayoutSettings) {
+        let area = if layout.name() == ArrangeEngine::Tabbed.name() {
+            Area {
+                y: AREA.y + settings.tab_bar_height,
+                h: AREA.h - settings.tab_bar_height,
+                ..AREA
+            }
+        } else {
+            AREA
+        };
+        let stacked = layout.name() == ArrangeEngine::Mono.name()
+            || layout.name() == ArrangeEngine::Tabbed.name();
+        let outer_gap = if stacked && settings.smart_gaps {
+            0
+        } else {
+            settings.outer_gap
+        };
+        for n in 1..=5 {
+            let clients: Vec<Client> = (0..n)
+                .map(|_| Client {
+                    weight: 1.0,
+                    ..Default::default()
+                })
+                .collect();
+            let clients: Vec<&Client> = clients.iter().collect();
+            let tiles: Vec<Area> = layout
+                .arrange(AREA, settings, &clients)
+                .iter()
+                .map(|g| footprint(&g.expect("client is tiled")))
+                .collect();
+            assert_eq!(tiles.len(), n, "{} with {n} clients", layout.name());
+            for (i, tile) in tiles.iter().enumerate() {
+                assert!(
+                    contains(area.shrink(outer_gap), *tile),
+                    "{} with {n} clients: {tile:?} outside of outer gap",
+                    layout.name()
+                );
+                if !stacked {
+                    for other in &tiles[i + 1..] {
+                        assert!(
+                            !overlaps(*tile, *other),
+                            "{} with {n} clients: {tile:?} overlaps {other:?}",
+                            layout.name()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_stay_inside_area() {
+        for position in [MasterPosition::Left, MasterPosition::Center] {
+            for layout in builtin_layouts() {
+                check(layout.as_ref(), &settings(position));
+            }
+        }
+    }
+
+    #[test]
+    fn smart_settings_drop_gaps_of_single_window_layouts() {
+        let settings = LayoutSettings {
+            smart_gaps: true,
+            smart_borders: true,
+            ..settings(MasterPosition::Left)
+        };
+        for layout in builtin_layouts() {
+            check(layout.as_ref(), &settings);
+        }
+    }
+
+    /// No gaps & borders so expected geometry is easy to tell
+    fn plain() -> LayoutSettings {
+        LayoutSettings {
+            gap: 0,
+            outer_gap: 0,
+            border: 0,
+            master_width: 0.5,
+            ..settings(MasterPosition::Left)
+        }
+    }
+
+    fn area(x: i32, y: i32, w: i32, h: i32) -> Area {
+        Area { x, y, w, h }
+    }
+
+    /// Footprints of `n` clients arranged in 1600x800 area
+    fn arrange(layout: &dyn Layout, settings: &LayoutSettings, n: usize) -> Vec<Area> {
+        let clients: Vec<Client> = (0..n)
+            .map(|_| Client {
+                weight: 1.0,
+                ..Default::default()
+            })
+            .collect();
+        let clients: Vec<&Client> = clients.iter().collect();
+        layout
+            .arrange(area(0, 0, 1600, 800), settings, &clients)
+            .iter()
+            .map(|g| footprint(&g.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn grid_fills_rows_first() {
+        let row_sizes = |n| {
+            let mut rows: Vec<(i32, usize)> = vec![];
+            for tile in arrange(&Grid, &plain(), n) {
+                match rows.last_mut() {
+                    Some((y, count)) if *y == tile.y => *count += 1,
+                    _ => rows.push((tile.y, 1)),
+                }
+            }
+            rows.into_iter().map(|(_, count)| count).collect::<Vec<_>>()
+        };
+        assert_eq!(row_sizes(1), [1]);
+        assert_eq!(row_sizes(4), [2, 2]);
+        assert_eq!(row_sizes(5), [3, 2]);
+        assert_eq!(row_sizes(8), [3, 3, 2]);
+        // Last row is stretched
+        let tiles = arrange(&Grid, &plain(), 5);
+        assert_eq!(tiles[3], area(0, 400, 800, 400));
+        assert_eq!(tiles[4], area(800, 400, 800, 400));
+    }
+
+    #[test]
+    fn centered_master_alternates_right_and_left() {
+        let tiles = arrange(&CenteredMaster, &plain(), 5);
+        let master = tiles[0];
+        assert_eq!(master, area(400, 0, 800, 800));
+        assert_eq!(tiles[1], area(1200, 0, 400, 400));
+        assert_eq!(tiles[2], area(0, 0, 400, 400));
+        assert_eq!(tiles[3], area(1200, 400, 400, 400));
+        assert_eq!(tiles[4], area(0, 400, 400, 400));
+        // With single stack client master goes left
+        let tiles = arrange(&CenteredMaster, &plain(), 2);
+        assert_eq!(tiles, [area(0, 0, 800, 800), area(800, 0, 800, 800)]);
+    }
+
+    #[test]
+    fn three_column_master_position() {
+        let left = arrange(&ThreeColumn, &plain(), 4);
+        assert_eq!(
+            left,
+            [
+                area(0, 0, 800, 800),
+                area(800, 0, 400, 400),
+                area(800, 400, 400, 400),
+                area(1200, 0, 400, 800),
+            ]
+        );
+        let center = LayoutSettings {
+            three_column_master: MasterPosition::Center,
+            ..plain()
+        };
+        assert_eq!(
+            arrange(&ThreeColumn, &center, 4),
+            [
+                area(400, 0, 800, 800),
+                area(1200, 0, 400, 400),
+                area(1200, 400, 400, 400),
+                area(0, 0, 400, 800),
+            ]
+        );
+        // Less than two stack clients is Tiled
+        assert_eq!(
+            arrange(&ThreeColumn, &center, 2),
+            arrange(&Tiled, &center, 2)
+        );
+    }
+
+    #[test]
+    fn bottom_stack_master_height() {
+        let settings = LayoutSettings {
+            master_width: 0.6,
+            ..plain()
+        };
+        assert_eq!(
+            arrange(&BottomStack, &settings, 3),
+            [
+                area(0, 0, 1600, 480),
+                area(0, 480, 800, 320),
+                area(800, 480, 800, 320),
+            ]
+        );
+        assert_eq!(arrange(&BottomStack, &settings, 1), [area(0, 0, 1600, 800)]);
+    }
+
+    #[test]
+    fn spiral_goes_around_and_dwindle_goes_to_corner() {
+        assert_eq!(
+            arrange(&Fibonacci { spiral: false }, &plain(), 5),
+            [
+                area(0, 0, 800, 800),
+                area(800, 0, 800, 400),
+                area(800, 400, 400, 400),
+                area(1200, 400, 400, 200),
+                area(1200, 600, 400, 200),
+            ]
+        );
+        assert_eq!(
+            arrange(&Fibonacci { spiral: true }, &plain(), 5),
+            [
+                area(0, 0, 800, 800),
+                area(800, 0, 800, 400),
+                area(1200, 400, 400, 400),
+                area(800, 600, 400, 200),
+                area(800, 400, 400, 200),
+            ]
+        );
+    }
+
+    #[test]
+    fn stack_heights_follow_weights() {
+        let weights = [1.0, 2.0, 1.0, 1.0];
+        let clients: Vec<Client> = weights
+            .iter()
+            .map(|&weight| Client {
+                weight,
+                ..Default::default()
+            })
+            .collect();
+        let clients: Vec<&Client> = clients.iter().collect();
+        let heights = |settings: &LayoutSettings| -> Vec<u32> {
+            Tiled
+                .arrange(area(0, 0, 1600, 1000), settings, &clients)
+                .iter()
+                .map(|g| g.unwrap().h)
+                .collect()
+        };
+        assert_eq!(heights(&plain()), [1000, 500, 250, 250]);
+        // Gaps are taken before space is shared
+        let gaps = LayoutSettings {
+            gap: 10,
+            outer_gap: 10,
+            ..plain()
+        };
+        assert_eq!(heights(&gaps), [980, 480, 240, 240]);
+    }
+
+    #[test]
+    fn unset_weight_counts_as_one() {
+        let clients = [
+            Client::default(),
+            Client::default(),
+            Client {
+                weight: 2.0,
+                ..Default::default()
+            },
+        ];
+        let clients: Vec<&Client> = clients.iter().collect();
+        let heights: Vec<u32> = weighted_column(area(0, 0, 800, 800), &clients, 0, 0)
+            .iter()
+            .map(|g| g.h)
+            .collect();
+        assert_eq!(heights, [200, 200, 400]);
+    }
+}
@@ -0,0 +1,180 @@
+//! Command line client for rtwm control socket
+//!
+//! Sends single command to running WM, prints JSON reply and exits with non-zero code
+//! if WM could not be reached or command failed.
+
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::exit;
+
+use serde_json::json;
+use serde_json::Value;
+
+#[path = "../socket.rs"]
+mod socket;
+
+use socket::camel_case;
+use socket::socket_path;
+
+const USAGE: &str = "Usage: rtwmc [-s SOCKET] COMMAND [ARGS...]
+
+Commands:
+    workspace N             Focus workspace N (counted from 0) on current screen
+    move-to-workspace N     Move focused window to workspace N
+    screen next|prev        Focus next/previous screen
+    move-to-screen next|prev
+                            Move focused window to next/previous screen
+    layout NAME|next|prev   Set layout of current workspace:
+                            tiled, mono, grid, centered-master, bottom-stack,
+                            spiral, dwindle, tabbed, three-column
+                            or switch to next/previous one of layout_cycle
+    float                   Toggle floating state of focused window
+    kill                    Close focused window
+    spawn CMD [ARGS...]     Run program
+    reload                  Reload configuration
+    script NAME             Run ~/.config/rtwm/scripts/NAME.rhai
+    quit                    Exit window manager
+    query [state|screens|clients|focus]
+                            Print WM state as JSON
+    subscribe [EVENT...]    Print events as they happen, one JSON per line.
+                            Events: workspace-focused, window-opened, window-closed,
+                            window-focused, window-title-changed, urgency-changed,
+                            layout-changed, screens-changed
+
+Any other action is sent by name, e.g. `rtwmc update-master-width 0.05`
+
+Options:
+    -s, --socket SOCKET     Socket path, defaults to $RTWM_SOCKET
+    -h, --help              Print this message";
+
+/// Translate client command into JSON understood by WM. Arguments are kept as given,
+/// so they never go through whitespace splitting of text commands
+fn build_command(args: &[String]) -> Result<Value, String> {
+    let direction = |arg: Option<&String>| match arg.map(|a| a.as_str()) {
+        Some("next") => Ok("Next"),
+        Some("prev") | Some("previous") => Ok("Previous"),
+        _ => Err("expected `next` or `prev`".to_string()),
+    };
+    let number = |arg: Option<&String>| match arg.map(|a| a.parse::<u64>()) {
+        Some(Ok(n)) => Ok(n),
+        _ => Err("expected workspace number".to_string()),
+    };
+
+    let command = match args[0].as_str() {
+        "workspace" => json!({ "FocusOnWorkspace": number(args.get(1))? }),
+        "move-to-workspace" => json!({ "MoveToWorkspace": number(args.get(1))? }),
+        "screen" => json!({ "FocusOnScreen": direction(args.get(1))? }),
+        "move-to-screen" => json!({ "MoveToScreen": direction(args.get(1))? }),
+        "layout" => match args.get(1).map(|a| a.as_str()) {
+            Some("next") => json!("NextLayout"),
+            Some("prev") | Some("previous") => json!("PrevLayout"),
+            // WM resolves built-in names itself
+            Some(layout) => json!({ "SetArrangeEngine": { "Custom": layout } }),
+            None => return Err("expected layout name".to_string()),
+        },
+        "float" => json!("ToggleFloat"),
+        "kill" => json!("KillClient"),
+        "reload" => json!("ReloadConfig"),
+        "spawn" if args.len() < 2 => return Err("expected command to spawn".to_string()),
+        "spawn" => json!({ "Spawn": &args[1..] }),
+        "script" => match args.get(1) {
+            Some(name) => json!({ "Script": name }),
+            None => return Err("expected script name".to_string()),
+        },
+        "query" => json!({ "query": args.get(1).map_or("state", |a| a.as_str()) }),
+        "subscribe" => json!({ "subscribe": &args[1..] }),
+        // Any other action: numbers as is, words as enum variants, several words as list
+        name => {
+            let name = camel_case(name);
+            match &args[1..] {
+                [] => json!(name),
+                [arg] => {
+                    let value = serde_json::from_str::<Value>(arg)
+                        .ok()
+                        .filter(|v| v.is_number() || v.is_boolean())
+                        .unwrap_or_else(|| json!(camel_case(arg)));
+                    json!({ name: value })
+                }
+                rest => json!({ name: rest }),
+            }
+        }
+    };
+    Ok(command)
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut socket = socket_path();
+
+    match args.first().map(|a| a.as_str()) {
+        None | Some("-h") | Some("--help") => {
+            println!("{USAGE}");
+            exit(if args.is_empty() { 2 } else { 0 });
+        }
+        Some("-s") | Some("--socket") => {
+            if args.len() < 3 {
+                eprintln!("{USAGE}");
+                exit(2);
+            }
+            socket = PathBuf::from(&args[1]);
+            args.drain(..2);
+        }
+        _ => {}
+    }
+
+    let command = match build_command(&args) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("rtwmc: {}: {e}", args[0]);
+            exit(2);
+        }
+    };
+
+    let mut stream = match UnixStream::connect(&socket) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("rtwmc: can't connect to {}: {e}", socket.display());
+            exit(2);
+        }
+    };
+    if let Err(e) = writeln!(stream, "{command}") {
+        eprintln!("rtwmc: failed to send command: {e}");
+        exit(2);
+    }
+
+    let mut reader = BufReader::new(&stream);
+    let mut reply = String::new();
+    if let Err(e) = reader.read_line(&mut reply) {
+        eprintln!("rtwmc: failed to read reply: {e}");
+        exit(2);
+    }
+    let reply = reply.trim();
+    println!("{reply}");
+
+    let success = serde_json::from_str::<serde_json::Value>(reply)
+        .ok()
+        .and_then(|v| v.get("success").and_then(|s| s.as_bool()))
+        .unwrap_or(false);
+    if !success {
+        exit(1);
+    }
+
+    // Subscription keeps connection open, print events until WM closes it
+    if args[0] == "subscribe" {
+        let stdout = std::io::stdout();
+        for line in reader.lines() {
+            match line {
+                Ok(line) => {
+                    let mut out = stdout.lock();
+                    if writeln!(out, "{line}").and_then(|_| out.flush()).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Validation of configuration, used by `rtwm --check-config`
+
+use std::ffi::CString;
+
+use crate::config::NUMBER_OF_DESKTOPS;
+use crate::helper::match_modifier;
+use crate::layout::load_layouts;
+use crate::loader::*;
+use crate::script::script_path;
+use crate::structs::*;
+
+/// Returns list of problems found in configuration
+///
+/// 1. Duplicate key bindings
+/// 2. Empty `Spawn` commands
+/// 3. Desktops arrays not matching [`NUMBER_OF_DESKTOPS`]
+/// 4. Placement & autostart rules pointing at workspaces that can't exist,
+///    screens are checked by [`config_notes`]
+/// 5. Bindings running scripts that don't exist
+/// 6. Layouts that are not registered
+pub fn check_config(config: &Configuration) -> Vec<String> {
+    let mut problems = vec![];
+
+    // 1. Duplicates
+    for (i, a) in config.key_actions.iter().enumerate() {
+        if let Some(j) = config.key_actions[..i]
+            .iter()
+            .position(|b| a.keysym == b.keysym && match_modifier(a.modifier, b.modifier))
+        {
+            problems.push(format!(
+                "{}: `{}` is already bound by {} ({:?}), {:?} is never run",
+                binding_name(config, i),
+                key_to_string(a.modifier, a.keysym),
+                binding_name(config, j),
+                config.key_actions[j].result,
+                a.result,
+            ));
+        }
+    }
+
+    // 2. Empty commands
+    for (i, action) in config.key_actions.iter().enumerate() {
+        if let ActionResult::Spawn(cmd) = &action.result {
+            if is_empty_command(cmd) {
+                problems.push(format!(
+                    "{}: `{}` spawns empty command",
+                    binding_name(config, i),
+                    key_to_string(action.modifier, action.keysym)
+                ));
+            }
+        }
+    }
+    for (i, rule) in config.autostart.iter().enumerate() {
+        if is_empty_command(&rule.cmd) {
+            problems.push(format!("autostart[{i}]: empty command"));
+        }
+    }
+
+    // 3. Desktops
+    if config.desktops.keysyms.len() != NUMBER_OF_DESKTOPS {
+        problems.push(format!(
+            "desktops.keysyms: has {} keysyms for {} desktops",
+            config.desktops.keysyms.len(),
+            NUMBER_OF_DESKTOPS
+        ));
+    }
+    for (screen, names) in config.desktops.names.iter().enumerate() {
+        if names.len() != NUMBER_OF_DESKTOPS {
+            problems.push(format!(
+                "desktops.names[{screen}]: has {} names for {} desktops",
+                names.len(),
+                NUMBER_OF_DESKTOPS
+            ));
+        }
+    }
+    for (screen, splits) in config.desktops.splits.iter().enumerate() {
+        if splits.len() != NUMBER_OF_DESKTOPS {
+            problems.push(format!(
+                "desktops.splits[{screen}]: has {} splits for {} desktops",
+                splits.len(),
+                NUMBER_OF_DESKTOPS
+            ));
+        }
+    }
+
+    for (screen, layouts) in config.desktops.layouts.iter().enumerate() {
+        if layouts.len() > NUMBER_OF_DESKTOPS {
+            problems.push(format!(
+                "desktops.layouts[{screen}]: has {} layouts for {} desktops",
+                layouts.len(),
+                NUMBER_OF_DESKTOPS
+            ));
+        }
+    }
+    for (screen, capacities) in config.desktops.master_capacities.iter().enumerate() {
+        if capacities.len() > NUMBER_OF_DESKTOPS {
+            problems.push(format!(
+                "desktops.master_capacities[{screen}]: has {} capacities for {} desktops",
+                capacities.len(),
+                NUMBER_OF_DESKTOPS
+            ));
+        }
+    }
+    for (screen, gaps) in config.desktops.gaps.iter().enumerate() {
+        if gaps.len() > NUMBER_OF_DESKTOPS {
+            problems.push(format!(
+                "desktops.gaps[{screen}]: has {} gaps for {} desktops",
+                gaps.len(),
+                NUMBER_OF_DESKTOPS
+            ));
+        }
+    }
+
+    // 4. Rules
+    for (i, rule) in config.placements.iter().enumerate() {
+        if let Some(w) = rule.rule_workspace {
+            if w >= NUMBER_OF_DESKTOPS {
+                problems.push(format!(
+                    "placements[{i}]: workspace {w} does not exist, there are {} desktops per screen",
+                    NUMBER_OF_DESKTOPS
+                ));
+            }
+        }
+        if rule.instance.is_none() && rule.class.is_none() && rule.title.is_none() {
+            problems.push(format!(
+                "placements[{i}]: has no instance, class or title and matches every window"
+            ));
+        }
+    }
+    for (i, rule) in config.autostart.iter().enumerate() {
+        if let Some((_, w)) = rule.rule {
+            if w >= NUMBER_OF_DESKTOPS {
+                problems.push(format!(
+                    "autostart[{i}]: workspace {w} does not exist, there are {} desktops per screen",
+                    NUMBER_OF_DESKTOPS
+                ));
+            }
+        }
+    }
+
+    // 5. Scripts
+    for (i, action) in config.key_actions.iter().enumerate() {
+        if let ActionResult::Script(name) = &action.result {
+            match script_path(name) {
+                Some(path) if path.is_file() => {}
+                Some(path) => problems.push(format!(
+                    "{}: `{}` runs script {} that does not exist",
+                    binding_name(config, i),
+                    key_to_string(action.modifier, action.keysym),
+                    path.display()
+                )),
+                None => {}
+            }
+        }
+    }
+
+    // 6. Layouts
+    let registry = load_layouts();
+    let names = registry.names().join(", ");
+    for (i, action) in config.key_actions.iter().enumerate() {
+        if let ActionResult::SetArrangeEngine(ae) = &action.result {
+            if registry.get(ae.name()).is_none() {
+                problems.push(format!(
+                    "{}: `{}` sets unknown layout `{}`, available: {names}",
+                    binding_name(config, i),
+                    key_to_string(action.modifier, action.keysym),
+                    ae.name()
+                ));
+            }
+        }
+    }
+    for (i, ae) in config.layout_cycle.iter().enumerate() {
+        if registry.get(ae.name()).is_none() {
+            problems.push(format!(
+                "layout_cycle[{i}]: unknown layout `{}`, available: {names}",
+                ae.name()
+            ));
+        }
+    }
+    for (screen, layouts) in config.desktops.layouts.iter().enumerate() {
+        for (workspace, ae) in layouts.iter().enumerate() {
+            if registry.get(ae.name()).is_none() {
+                problems.push(format!(
+                    "desktops.layouts[{screen}][{workspace}]: unknown layout `{}`, available: {names}",
+                    ae.name()
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+/// Returns notes about things that can't be validated without display
+///
+/// Amount of screens is only known once monitors are queried, so rules pointing at screens
+/// without per-screen `desktops` entries are reported here instead of [`check_config`]
+pub fn config_notes(config: &Configuration) -> Vec<String> {
+    let mut notes = vec![];
+    let screens = config
+        .desktops
+        .names
+        .len()
+        .max(config.desktops.splits.len());
+    let rules = config
+        .placements
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| r.rule_screen.map(|s| (format!("placements[{i}]"), s)))
+        .chain(
+            config
+                .autostart
+                .iter()
+                .enumerate()
+                .filter_map(|(i, r)| r.rule.map(|(s, _)| (format!("autostart[{i}]"), s))),
+        );
+    for (rule, screen) in rules {
+        if screens == 0 {
+            notes.push(format!(
+                "{rule}: screen {screen} can't be checked, screen count is unknown without display"
+            ));
+        } else if screen >= screens {
+            notes.push(format!(
+                "{rule}: screen {screen} has no desktops.names/splits entry ({screens} configured), \
+                 rule only applies with at least {} monitors connected",
+                screen + 1
+            ));
+        }
+    }
+    notes
+}
+
+/// Where binding comes from, for messages. Workspace bindings generated from
+/// `desktops.keysyms` are last in `key_actions`, focus & move binding for every keysym
+fn binding_name(config: &Configuration, i: usize) -> String {
+    let keysyms = &config.desktops.keysyms;
+    let first = config.key_actions.len().saturating_sub(2 * keysyms.len());
+    if i >= first {
+        let k = (i - first) / 2;
+        let action = &config.key_actions[i];
+        if action.keysym == keysyms[k] {
+            match action.result {
+                ActionResult::FocusOnWorkspace(w) if w as usize == k => {
+                    return format!("desktops.keysyms[{k}] with focus_modifier");
+                }
+                ActionResult::MoveToWorkspace(w) if w as usize == k => {
+                    return format!("desktops.keysyms[{k}] with move_modifier");
+                }
+                _ => {}
+            }
+        }
+    }
+    format!("key_actions[{i}]")
+}
+
+fn is_empty_command(cmd: &[CString]) -> bool {
+    match cmd.first() {
+        Some(program) => program.as_bytes().iter().all(|b| b.is_ascii_whitespace()),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_bindings_are_named_after_keysyms() {
+        let config = parse_config(
+            r#"
+            key_actions = [{ key = "Mod4+2", result = "KillClient" }]
+            [desktops]
+            focus_modifier = "Mod4"
+            move_modifier = "Mod4+Shift"
+            "#,
+        )
+        .unwrap();
+        let problems = check_config(&config);
+        assert!(
+            problems.contains(
+                &"desktops.keysyms[1] with focus_modifier: `Mod4+2` is already bound by \
+                  key_actions[0] (KillClient), FocusOnWorkspace(1) is never run"
+                    .to_string()
+            ),
+            "{problems:?}"
+        );
+        assert!(!problems.iter().any(|p| p.contains("move_modifier")));
+    }
+}
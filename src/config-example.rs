@@ -4,15 +4,20 @@
 use std::ffi::CString;
 
 use crate::structs::ActionResult::*;
+use crate::structs::ArrangeEngine;
 use crate::structs::AutostartRuleCMD;
 use crate::structs::Color;
 use crate::structs::Configuration;
 use crate::structs::DesktopsConfig;
 use crate::structs::KeyAction;
+use crate::structs::MasterPosition;
 use crate::structs::PlacementRule;
 use crate::structs::ScreenSwitching;
+use crate::structs::TabBarConfig;
+use crate::layout::Layout;
 
 use x11::keysym::*;
+use x11::xlib::ControlMask;
 use x11::xlib::Mod4Mask as ModKey;
 use x11::xlib::ShiftMask;
 
@@ -69,7 +74,12 @@ pub fn config() -> Configuration {
     //-----------------------------------------------------------------------
     //                               Visuals
     //-----------------------------------------------------------------------
+    // Gaps between windows & between windows and screen edge
     let gap_width = 4;
+    let outer_gap_width = 4;
+    // Hide gaps & border when single tiled window is visible (also in Mono)
+    let smart_gaps = true;
+    let smart_borders = true;
     let border_size = 2;
     let normal_border_color = Color {
         //#404080
@@ -92,6 +102,32 @@ pub fn config() -> Configuration {
         green: 28,
         blue: 28,
     };
+    // Master column of ThreeColumn layout, Left or Center
+    let three_column_master = MasterPosition::Center;
+    // Layouts switched by NextLayout & PrevLayout, in order
+    let layout_cycle = vec![
+        ArrangeEngine::Tiled,
+        ArrangeEngine::Mono,
+        ArrangeEngine::Tabbed,
+        ArrangeEngine::Grid,
+        ArrangeEngine::CenteredMaster,
+        ArrangeEngine::ThreeColumn,
+        ArrangeEngine::BottomStack,
+        ArrangeEngine::Spiral,
+        ArrangeEngine::Dwindle,
+    ];
+    // Tab strip of Tabbed layout, tabs are colored like borders
+    let tab_bar = TabBarConfig {
+        height: 20,
+        font: "-*-fixed-medium-r-*-*-13-*-*-*-*-*-*-*".to_string(),
+        text_color: Color {
+            //#eeeeee
+            alpha: 255,
+            red: 238,
+            green: 238,
+            blue: 238,
+        },
+    };
 
     //-----------------------------------------------------------------------
     //                          Shortcuts setup
@@ -162,6 +198,11 @@ pub fn config() -> Configuration {
             keysym: XK_w,
             result: DumpInfo,
         },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_r,
+            result: ReloadConfig,
+        },
         KeyAction {
             modifier: ModKey,
             keysym: XK_comma,
@@ -202,6 +243,21 @@ pub fn config() -> Configuration {
             keysym: XK_h,
             result: UpdateMasterWidth(-0.05),
         },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_l,
+            result: UpdateClientWeight(0.25),
+        },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_h,
+            result: UpdateClientWeight(-0.25),
+        },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_o,
+            result: UpdateClientWeight(0.0),
+        },
         KeyAction {
             modifier: ModKey | ShiftMask | ControlMask,
             keysym: XK_Return,
@@ -212,6 +268,16 @@ pub fn config() -> Configuration {
             keysym: XK_space,
             result: ToggleFloat,
         },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_space,
+            result: NextLayout,
+        },
+        KeyAction {
+            modifier: ModKey | ControlMask,
+            keysym: XK_space,
+            result: PrevLayout,
+        },
         KeyAction {
             modifier: ModKey,
             keysym: XK_j,
@@ -229,17 +295,30 @@ pub fn config() -> Configuration {
     //-----------------------------------------------------------------------
     let mut desktops = DesktopsConfig::new();
 
-    desktops.keysyms = [XK_1, XK_2, XK_3, XK_4, XK_5, XK_6, XK_7, XK_8, XK_9, XK_0];
+    desktops.keysyms = vec![
+        XK_1, XK_2, XK_3, XK_4, XK_5, XK_6, XK_7, XK_8, XK_9, XK_0, XK_minus,
+    ];
 
-    desktops.names =
-        vec![["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"].map(|s| s.to_string())];
+    desktops.names = vec![["1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11"]
+        .map(|s| s.to_string())
+        .to_vec()];
 
-    // desktops.splits is a vector of arrays of NUMBER_OF_DESKTOPS float numbers where
-    // each array corresponds to screen and each float corresponds to split on workspace
+    // desktops.splits is a vector of vectors of NUMBER_OF_DESKTOPS float numbers where
+    // each vector corresponds to screen and each float corresponds to split on workspace
     // so: desktops.splits[screen index][workspace index] = split width
-    desktops.splits = vec![[0.5; NUMBER_OF_DESKTOPS]]
+    desktops.splits = vec![vec![0.5; NUMBER_OF_DESKTOPS]];
     desktops.splits[0][0] = 0.7;
 
+    // desktops.layouts has same shape as desktops.splits and sets initial layout of workspace,
+    // missing ones are ArrangeEngine::Tiled. Use BottomStack for portrait monitors
+    // desktops.layouts = vec![vec![ArrangeEngine::Tiled; NUMBER_OF_DESKTOPS]];
+
+    // desktops.master_capacities sets initial master capacity the same way, 1 if not set
+    // desktops.master_capacities = vec![vec![1; NUMBER_OF_DESKTOPS]];
+
+    // desktops.gaps replaces gap_width & outer_gap_width on workspace, None keeps them
+    // desktops.gaps = vec![vec![None; NUMBER_OF_DESKTOPS]];
+
     for (i, k) in desktops.keysyms.iter().enumerate() {
         key_actions.push(KeyAction {
             modifier: ModKey,
@@ -293,15 +372,29 @@ pub fn config() -> Configuration {
     //-----------------------------------------------------------------------
     //                      Create config & return
     //-----------------------------------------------------------------------
-    return Configuration {
+    Configuration {
         key_actions,
         gap_width,
+        outer_gap_width,
+        smart_gaps,
+        smart_borders,
         border_size,
         normal_border_color,
         active_border_color,
         urgent_border_color,
+        three_column_master,
+        layout_cycle,
+        tab_bar,
         desktops,
         autostart,
         placements,
-    };
+    }
+}
+
+/// Own layouts, selected with `SetArrangeEngine(ArrangeEngine::Custom("name".to_string()))`.
+/// Layout with name of built-in one replaces it, see [`crate::layout::Layout`]
+pub fn layouts() -> Vec<Box<dyn Layout>> {
+    vec![
+        // Box::new(MyLayout),
+    ]
 }
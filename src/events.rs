@@ -7,9 +7,11 @@ use crate::logic::*;
 use crate::manage::*;
 use crate::mouse::*;
 use crate::structs::*;
+use crate::tabs::*;
 use crate::utils::*;
 use crate::wrapper::xlib::*;
 
+use x11::xlib::Atom;
 use x11::xlib::Button1;
 use x11::xlib::Button3;
 use x11::xlib::CWBorderWidth;
@@ -33,7 +35,6 @@ use x11::xlib::XUnmapEvent;
 use x11::xlib::XWindowChanges;
 use x11::xlib::CWX;
 use x11::xlib::CWY;
-use x11::xlib::XA_ATOM;
 
 pub fn key_press(app: &mut Application, key_event: XKeyEvent) {
     // Iterate over key actions matching current key input
@@ -41,66 +42,7 @@ pub fn key_press(app: &mut Application, key_event: XKeyEvent) {
         if key_event.keycode == keysym_to_keycode(app.core.display, action.keysym)
             && match_modifier(key_event.state, action.modifier)
         {
-            // Match action result and run related function
-            match &action.result {
-                ActionResult::KillClient => {
-                    kill_client(app);
-                }
-                ActionResult::Spawn(cmd) => {
-                    spawn(app, &cmd.clone(), None);
-                }
-                ActionResult::MoveToScreen(d) => {
-                    move_to_screen(app, *d);
-                }
-                ActionResult::FocusOnScreen(d) => {
-                    focus_on_screen(app, *d);
-                }
-                ActionResult::MoveToWorkspace(n) => {
-                    move_to_workspace(app, *n);
-                }
-                ActionResult::FocusOnWorkspace(n) => {
-                    focus_on_workspace(app, *n, true);
-                }
-                ActionResult::Quit => {
-                    app.core.running = false;
-                }
-                ActionResult::UpdateMasterCapacity(i) => {
-                    update_master_capacity(app, *i);
-                }
-                ActionResult::UpdateMasterWidth(w) => {
-                    update_master_width(app, *w);
-                }
-                ActionResult::DumpInfo => {
-                    log!("{:#?}", &app.runtime);
-                }
-                ActionResult::ToggleFloat => {
-                    toggle_float(app);
-                }
-                ActionResult::CycleStack(d) => {
-                    cycle_stack(app, *d);
-                }
-                ActionResult::PopPushStack => {
-                    pop_push_stack(app, true);
-                }
-                ActionResult::RotateStack => {
-                    pop_push_stack(app, false);
-                }
-                ActionResult::SetArrangeEngine(ae) => {
-                    app.runtime.screens[app.runtime.current_screen].workspaces
-                        [app.runtime.current_workspace]
-                        .arrange = ae.clone();
-                    arrange_workspace(
-                        app,
-                        app.runtime.current_screen,
-                        app.runtime.current_workspace,
-                    );
-                    show_workspace(
-                        app,
-                        app.runtime.current_screen,
-                        app.runtime.current_workspace,
-                    );
-                }
-            }
+            run_action(app, &action.result);
         }
     }
 }
@@ -118,7 +60,7 @@ pub fn enter_notify(app: &mut Application, crossing_event: XCrossingEvent) {
         if crossing_event.mode != NotifyNormal || crossing_event.detail == NotifyInferior {
             return;
         }
-        if find_window_indexes(app, ew).is_none() {
+        if !accepts_focus(app, ew) {
             return;
         }
         if let Some(cw) = get_current_client_id(app) {
@@ -184,16 +126,53 @@ pub fn motion_notify(
 }
 
 pub fn property_notify(app: &mut Application, property_event: XPropertyEvent) {
-    if property_event.window != app.core.root_win {
-        update_client_name(app, property_event.window);
+    if property_event.window == app.core.root_win {
+        return;
+    }
+    if (property_event.atom == app.atoms.net_wm_strut_partial
+        || property_event.atom == app.atoms.net_wm_strut)
+        && update_dock_strut(app, property_event.window)
+    {
+        log!("|- Dock {} changed its strut", property_event.window);
+        return;
     }
+    update_client_name(app, property_event.window);
+}
+
+/// Monitor was connected, disconnected or changed its mode. Ran by main loop once
+/// for all RandR & root `ConfigureNotify` events of one change
+///
+/// 1. Update screens & workspaces
+/// 2. Rearrange everything for new geometry
+/// 3. Show current workspaces, hide others
+pub fn screen_change_notify(app: &mut Application) {
+    log!("|- Got screen change -> Changing monitor layout");
+    // 1. Update
+    update_screens(app);
+    update_desktops(app);
+
+    // 2. Rearrange
+    arrange_all(app);
+
+    // 3. Show
+    for screen in 0..app.runtime.screens.len() {
+        let current = app.runtime.screens[screen].current_workspace;
+        for workspace in 0..app.runtime.screens[screen].workspaces.len() {
+            if workspace != current {
+                hide_workspace(app, screen, workspace);
+            }
+        }
+        show_workspace(app, screen, current);
+    }
+    update_tab_bars(app);
+    update_active_window(app);
+    suppress_notify(app);
 }
 
 pub fn configure_notify(app: &mut Application, configure_event: XConfigureEvent) {
     if configure_event.window == app.core.root_win {
-        log!("|- Got `ConfigureNotify` for `root window` -> Changing monitor layout");
-        update_screens(app);
-        update_desktops(app);
+        log!("|- Got `ConfigureNotify` for `root window`");
+        app.runtime.screens_changed = true;
     } else if let Some((s, w, c)) = find_window_indexes(app, configure_event.window) {
         let client = &app.runtime.screens[s].workspaces[w].clients[c];
         log!(
@@ -223,52 +202,58 @@ pub fn client_message(app: &mut Application, client_event: XClientMessageEvent)
             cc.window_name
         );
         if client_event.message_type == app.atoms.net_wm_state {
-            if client_event.data.get_long(1) as u64 == app.atoms.net_wm_fullscreen
-                || client_event.data.get_long(2) as u64 == app.atoms.net_wm_fullscreen
-            {
-                let sf = client_event.data.get_long(0) == 1
-                    || client_event.data.get_long(0) == 2 && cc.fullscreen;
-                if sf && !cc.fullscreen {
-                    change_property(
-                        app.core.display,
-                        client_event.window,
-                        app.atoms.net_wm_state,
-                        XA_ATOM,
-                        32,
-                        PropModeReplace,
-                        &mut app.atoms.net_wm_fullscreen as *mut u64 as *mut u8,
-                        1,
-                    );
-                    cc.ow = cc.w;
-                    cc.oh = cc.h;
-                    cc.w = client_screen.width as u32;
-                    cc.h = client_screen.height as u32;
-                    cc.fullscreen = true;
-                } else if !sf && cc.fullscreen {
-                    change_property(
-                        app.core.display,
-                        client_event.window,
-                        app.atoms.net_wm_state,
-                        XA_ATOM,
-                        32,
-                        PropModeReplace,
-                        std::ptr::null_mut::<u8>(),
-                        0,
-                    );
-                    cc.w = cc.ow;
-                    cc.h = cc.oh;
-                    cc.fullscreen = false;
+            // Up to two states are changed by one message
+            let win = client_event.window;
+            let action = client_event.data.get_long(0);
+            for state in [client_event.data.get_long(1), client_event.data.get_long(2)] {
+                if state != 0 {
+                    set_client_state(app, win, state as Atom, action);
                 }
-                arrange_visible(app);
-                show_workspace(
-                    app,
-                    app.runtime.current_screen,
-                    app.runtime.current_workspace,
-                );
-            } else {
-                log!("      |- Unsupported `state`");
+            }
+            update_net_wm_state(app, win);
+            // Minimized current client passes focus to next one that can take it
+            let current = app.runtime.screens[client_screen_index].workspaces
+                [client_workspace_index]
+                .current_client;
+            if current == Some(client_index) && !accepts_focus(app, win) {
+                unfocus(app, win);
+                let ws = &mut app.runtime.screens[client_screen_index].workspaces
+                    [client_workspace_index];
+                let num_clients = ws.clients.len();
+                ws.current_client = (1..num_clients)
+                    .map(|k| (client_index + k) % num_clients)
+                    .find(|&i| !ws.clients[i].hidden && !ws.clients[i].never_focus);
+                if client_screen_index == app.runtime.current_screen {
+                    shift_current_client(app, client_screen_index, client_workspace_index);
+                }
+            }
+            arrange_workspace(app, client_screen_index, client_workspace_index);
+            if client_workspace_index == app.runtime.screens[client_screen_index].current_workspace
+            {
+                show_workspace(app, client_screen_index, client_workspace_index);
             }
         } else if client_event.message_type == app.atoms.net_active_window {
+            // Taskbars activate minimized windows to restore them
+            if cc.hidden {
+                log!("   |- Restoring hidden window");
+                let win = cc.window_id;
+                cc.hidden = false;
+                update_net_wm_state(app, win);
+                arrange_workspace(app, client_screen_index, client_workspace_index);
+                if client_workspace_index
+                    == app.runtime.screens[client_screen_index].current_workspace
+                {
+                    show_workspace(app, client_screen_index, client_workspace_index);
+                    // Activated window takes focus
+                    if let Some(cw) = get_current_client_id(app) {
+                        unfocus(app, cw);
+                    }
+                    focus(app, win);
+                    raise_window(app.core.display, win);
+                    suppress_notify(app);
+                }
+                return;
+            }
             log!("=== GOT URGENCY TRIGGER");
             if client_workspace_index != client_screen.current_workspace && !cc.urgent {
                 log!("=== SET URGENCY FLAG");
@@ -401,6 +386,9 @@ pub fn button_press(
     button_event: XButtonEvent,
     _motion_event: XMotionEvent,
 ) {
+    if tab_bar_click(app, button_event.window, button_event.x) {
+        return;
+    }
     if let Some((s, w, c)) = find_window_indexes(app, button_event.window) {
         let cc = &app.runtime.screens[s].workspaces[w].clients[c];
         if cc.floating {
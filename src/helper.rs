@@ -6,7 +6,9 @@ use std::ptr::null_mut;
 
 use crate::config;
 use crate::config::FOCUS_IGNORES_GEOMETRY;
+use crate::ipc::emit_event;
 use crate::structs::*;
+use crate::tabs::*;
 use crate::utils::*;
 use crate::wrapper::xlib::*;
 
@@ -149,9 +151,241 @@ pub fn get_atom_prop(app: &mut Application, win: u64, prop: Atom) -> Atom {
     atom
 }
 
+/// Read up to `len` items of `CARDINAL` property, `None` if window has no such property
+pub fn get_cardinal_prop(
+    app: &mut Application,
+    win: u64,
+    prop: Atom,
+    len: i64,
+) -> Option<Vec<i64>> {
+    let mut actual_type: Atom = 0;
+    let mut actual_format: i32 = 0;
+    let mut nitems: u64 = 0;
+    let mut bytes_after: u64 = 0;
+    let mut property_return: *mut u8 = null_mut();
+    if get_window_property(
+        app.core.display,
+        win,
+        prop,
+        0,
+        len,
+        false,
+        XA_CARDINAL,
+        &mut actual_type,
+        &mut actual_format,
+        &mut nitems,
+        &mut bytes_after,
+        &mut property_return,
+    ) != Success as i32
+        || property_return.is_null()
+    {
+        return None;
+    }
+    // Format 32 items are stored as longs
+    let values = (actual_type == XA_CARDINAL && actual_format == 32).then(|| unsafe {
+        std::slice::from_raw_parts(property_return as *const i64, nitems as usize).to_vec()
+    });
+    unsafe { x11::xlib::XFree(property_return as *mut libc::c_void) };
+    values
+}
+
+/// Read atoms of `ATOM` list property, e.g. `_NET_WM_STATE`, first 64 are enough for any
+pub fn get_atom_list_prop(app: &mut Application, win: u64, prop: Atom) -> Vec<Atom> {
+    let mut actual_type: Atom = 0;
+    let mut actual_format: i32 = 0;
+    let mut nitems: u64 = 0;
+    let mut bytes_after: u64 = 0;
+    let mut property_return: *mut u8 = null_mut();
+    if get_window_property(
+        app.core.display,
+        win,
+        prop,
+        0,
+        64,
+        false,
+        XA_ATOM,
+        &mut actual_type,
+        &mut actual_format,
+        &mut nitems,
+        &mut bytes_after,
+        &mut property_return,
+    ) != Success as i32
+        || property_return.is_null()
+    {
+        return vec![];
+    }
+    let atoms = if actual_type == XA_ATOM && actual_format == 32 {
+        unsafe {
+            std::slice::from_raw_parts(property_return as *const Atom, nitems as usize).to_vec()
+        }
+    } else {
+        vec![]
+    };
+    unsafe { x11::xlib::XFree(property_return as *mut libc::c_void) };
+    atoms
+}
+
+/// Actions of `_NET_WM_STATE` client message
+pub const NET_WM_STATE_REMOVE: i64 = 0;
+pub const NET_WM_STATE_ADD: i64 = 1;
+pub const NET_WM_STATE_TOGGLE: i64 = 2;
+
+/// Add, remove or toggle single `_NET_WM_STATE` atom of client
+///
+/// Only updates flags, caller writes property back with [`update_net_wm_state`] & rearranges.
+/// Returns `false` if window is not managed or state is unsupported
+pub fn set_client_state(app: &mut Application, win: u64, state: Atom, action: i64) -> bool {
+    let (s, w, c) = match find_window_indexes(app, win) {
+        Some(indexes) => indexes,
+        None => return false,
+    };
+    let enable = |current: bool| match action {
+        NET_WM_STATE_REMOVE => Some(false),
+        NET_WM_STATE_ADD => Some(true),
+        NET_WM_STATE_TOGGLE => Some(!current),
+        _ => None,
+    };
+
+    // Urgency has own bookkeeping
+    if state == app.atoms.net_wm_state_demands_attention {
+        let urgent = app.runtime.screens[s].workspaces[w].clients[c].urgent;
+        if let Some(urgent) = enable(urgent) {
+            set_urgent(app, win, urgent);
+        }
+        return true;
+    }
+
+    let atoms = &app.atoms;
+    let screen = &mut app.runtime.screens[s];
+    let (screen_w, screen_h) = (screen.width as u32, screen.height as u32);
+    let client = &mut screen.workspaces[w].clients[c];
+    let flag = if state == atoms.net_wm_fullscreen {
+        &mut client.fullscreen
+    } else if state == atoms.net_wm_state_maximized_vert {
+        &mut client.maximized_vert
+    } else if state == atoms.net_wm_state_maximized_horz {
+        &mut client.maximized_horz
+    } else if state == atoms.net_wm_state_above {
+        &mut client.above
+    } else if state == atoms.net_wm_state_below {
+        &mut client.below
+    } else if state == atoms.net_wm_state_sticky {
+        &mut client.sticky
+    } else if state == atoms.net_wm_state_hidden {
+        &mut client.hidden
+    } else if state == atoms.net_wm_state_skip_taskbar {
+        &mut client.skip_taskbar
+    } else if state == atoms.net_wm_state_skip_pager {
+        &mut client.skip_pager
+    } else {
+        log!("      |- Unsupported `state`");
+        return false;
+    };
+    let value = match enable(*flag) {
+        Some(value) => value,
+        None => return false,
+    };
+    if *flag == value {
+        return true;
+    }
+    *flag = value;
+
+    // Above & below exclude each other
+    if value && state == atoms.net_wm_state_above {
+        client.below = false;
+    } else if value && state == atoms.net_wm_state_below {
+        client.above = false;
+    }
+    // Fullscreen remembers size to restore
+    if state == atoms.net_wm_fullscreen {
+        if value {
+            client.ow = client.w;
+            client.oh = client.h;
+            client.w = screen_w;
+            client.h = screen_h;
+        } else {
+            client.w = client.ow;
+            client.h = client.oh;
+        }
+    }
+    // Maximizing remembers position & size along its axis, floating clients get them back
+    if state == atoms.net_wm_state_maximized_vert {
+        if value {
+            client.unmaximized_vert = Some((client.y, client.h));
+        } else if let Some((y, h)) = client.unmaximized_vert.take() {
+            client.y = y;
+            client.h = h;
+        }
+    } else if state == atoms.net_wm_state_maximized_horz {
+        if value {
+            client.unmaximized_horz = Some((client.x, client.w));
+        } else if let Some((x, w)) = client.unmaximized_horz.take() {
+            client.x = x;
+            client.w = w;
+        }
+    }
+    true
+}
+
+/// Write `_NET_WM_STATE` of client from its flags
+pub fn update_net_wm_state(app: &mut Application, win: u64) {
+    let (s, w, c) = match find_window_indexes(app, win) {
+        Some(indexes) => indexes,
+        None => return,
+    };
+    let client = &app.runtime.screens[s].workspaces[w].clients[c];
+    let atoms = &app.atoms;
+    let mut states: Vec<Atom> = [
+        (client.fullscreen, atoms.net_wm_fullscreen),
+        (client.maximized_vert, atoms.net_wm_state_maximized_vert),
+        (client.maximized_horz, atoms.net_wm_state_maximized_horz),
+        (client.above, atoms.net_wm_state_above),
+        (client.below, atoms.net_wm_state_below),
+        (client.sticky, atoms.net_wm_state_sticky),
+        (client.hidden, atoms.net_wm_state_hidden),
+        (client.skip_taskbar, atoms.net_wm_state_skip_taskbar),
+        (client.skip_pager, atoms.net_wm_state_skip_pager),
+        (client.urgent, atoms.net_wm_state_demands_attention),
+    ]
+    .into_iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, atom)| atom)
+    .collect();
+    change_property(
+        app.core.display,
+        win,
+        app.atoms.net_wm_state,
+        XA_ATOM,
+        32,
+        PropModeReplace,
+        states.as_mut_ptr() as *mut u8,
+        states.len() as i32,
+    );
+}
+
+/// Border of floating client, notifications, menus & desktop windows get none
+pub fn floating_border(config: &Configuration, client: &Client) -> u32 {
+    if client.never_focus {
+        0
+    } else {
+        config.border_size as u32
+    }
+}
+
+/// Managed window that is not hidden & may take focus
+pub fn accepts_focus(app: &mut Application, win: u64) -> bool {
+    match find_window_indexes(app, win) {
+        Some((s, w, c)) => {
+            let client = &app.runtime.screens[s].workspaces[w].clients[c];
+            !client.hidden && !client.never_focus
+        }
+        None => false,
+    }
+}
+
 /// Updates client list property of WM
 /// 1. Delete present list
-/// 2. For every client on every workspace on every screen add client to list
+/// 2. For every client on every workspace on every screen add client to list, except desktop ones
 pub fn update_client_list(app: &mut Application) {
     // 1. Delete
     delete_property(
@@ -163,7 +397,7 @@ pub fn update_client_list(app: &mut Application) {
     // 2. Update
     for screen in &app.runtime.screens {
         for workspace in &screen.workspaces {
-            for client in &workspace.clients {
+            for client in workspace.clients.iter().filter(|c| !c.desktop) {
                 change_property(
                     app.core.display,
                     app.core.root_win,
@@ -242,11 +476,27 @@ pub fn update_normal_hints(app: &mut Application, c: &mut Client) {
 }
 
 /// Shows all windows on current workspace
+///
+/// Hidden clients stay off screen, clients kept above or below are restacked last
 pub fn show_workspace(app: &mut Application, screen: usize, workspace: usize) {
+    let screen_index = screen;
+    let window_decoration_offset = app.config.gap_width + app.config.border_size;
     let screen = &mut app.runtime.screens[screen];
     let workspace = &mut screen.workspaces.get_mut(workspace).unwrap();
     // Iterate over all clients
     for client in &mut workspace.clients {
+        if client.hidden {
+            move_resize_window(
+                app.core.display,
+                client.window_id,
+                -(2 * client.w as i32 + window_decoration_offset as i32),
+                0,
+                client.w,
+                client.h,
+            );
+            client.visible = false;
+            continue;
+        }
         // 10. Fullscreen window if needed
         if client.fullscreen {
             move_resize_window(
@@ -272,16 +522,33 @@ pub fn show_workspace(app: &mut Application, screen: usize, workspace: usize) {
             //    client.h,
             //);
             resize_client(app.core.display, client);
-            if client.floating {
+            if client.floating || client.maximized_vert || client.maximized_horz {
                 raise_window(app.core.display, client.window_id);
             }
         };
         client.visible = true;
     }
+    // 13. Restack, fullscreen stays on top
+    for client in workspace.clients.iter().filter(|c| !c.hidden) {
+        if client.below {
+            lower_window(app.core.display, client.window_id);
+        } else if client.above {
+            raise_window(app.core.display, client.window_id);
+        }
+    }
+    for client in workspace
+        .clients
+        .iter()
+        .filter(|c| c.fullscreen && !c.hidden)
+    {
+        raise_window(app.core.display, client.window_id);
+    }
+    update_tab_bar(app, screen_index);
 }
 
 /// Hides all windows on current workspace
 pub fn hide_workspace(app: &mut Application, screen: usize, workspace: usize) {
+    hide_workspace_tab_bar(app, screen, workspace);
     let window_decoration_offset = app.config.gap_width + app.config.border_size;
     let screen = &mut app.runtime.screens[screen];
     let workspace = &mut screen.workspaces.get_mut(workspace).unwrap();
@@ -301,11 +568,22 @@ pub fn hide_workspace(app: &mut Application, screen: usize, workspace: usize) {
 }
 
 /// Spawn new program by forking
+pub fn spawn<S: AsRef<CStr>>(app: &mut Application, args: &[S], rule: Option<(usize, usize)>) {
+    spawn_with_env(app, args, rule, &[]);
+}
+
+/// Spawn new program by forking with additional environment variables
 ///
 /// 1. Fork get child PID for rules
 /// 2. For child close connections from Parent
-/// 3. Spawn program using sh
-pub fn spawn<S: AsRef<CStr>>(app: &mut Application, args: &[S], rule: Option<(usize, usize)>) {
+/// 3. Set environment
+/// 4. Spawn program using sh
+pub fn spawn_with_env<S: AsRef<CStr>>(
+    app: &mut Application,
+    args: &[S],
+    rule: Option<(usize, usize)>,
+    env: &[(&str, String)],
+) {
     unsafe {
         match nix::unistd::fork() {
             Ok(nix::unistd::ForkResult::Parent { child }) => {
@@ -325,8 +603,14 @@ pub fn spawn<S: AsRef<CStr>>(app: &mut Application, args: &[S], rule: Option<(us
                         Ok(_) | Err(_) => {}
                     };
                 }
-                // 3. Run
+                // 3. Set environment
+                for (key, value) in env {
+                    std::env::set_var(key, value);
+                }
+                // 4. Run
                 let _ = nix::unistd::execvp(args[0].as_ref(), args);
+                // Never return into WM code if exec failed
+                libc::_exit(1);
             }
             Err(_) => {}
         }
@@ -402,6 +686,45 @@ pub fn get_client_workspace(app: &mut Application, win: u64) -> Option<(usize, u
     }
 }
 
+/// Update `_NET_DESKTOP_GEOMETRY` & `_NET_WORKAREA`
+///
+/// Work area of desktop is its screen without space reserved by docks
+pub fn update_workarea(app: &mut Application) {
+    let (width, height) = display_size(app.core.display);
+    let mut geometry: Vec<i64> = vec![width as i64, height as i64];
+    change_property(
+        app.core.display,
+        app.core.root_win,
+        app.atoms.net_desktop_geometry,
+        XA_CARDINAL,
+        32,
+        PropModeReplace,
+        geometry.as_mut_ptr() as *mut u8,
+        geometry.len() as i32,
+    );
+
+    let mut workareas: Vec<i64> = vec![];
+    for screen in &app.runtime.screens {
+        let ba = screen.bar_offsets;
+        for _ in &screen.workspaces {
+            workareas.push(screen.x + ba.left as i64);
+            workareas.push(screen.y + ba.up as i64);
+            workareas.push((screen.width - (ba.left + ba.right) as i64).max(0));
+            workareas.push((screen.height - (ba.up + ba.down) as i64).max(0));
+        }
+    }
+    change_property(
+        app.core.display,
+        app.core.root_win,
+        app.atoms.net_workarea,
+        XA_CARDINAL,
+        32,
+        PropModeReplace,
+        workareas.as_mut_ptr() as *mut u8,
+        workareas.len() as i32,
+    );
+}
+
 /// Update EWMH desktop properties
 pub fn update_desktop_ewmh_info(
     app: &mut Application,
@@ -475,7 +798,21 @@ pub fn set_urgent(app: &mut Application, win: u64, urg: bool) {
     log!("|- Setting urgency to {urg} for {win}");
 
     if let Some((s, w, c)) = find_window_indexes(app, win) {
-        app.runtime.screens[s].workspaces[w].clients[c].urgent = urg;
+        let client = &mut app.runtime.screens[s].workspaces[w].clients[c];
+        if client.urgent != urg {
+            client.urgent = urg;
+            emit_event(
+                app,
+                WmEvent::UrgencyChanged {
+                    window_id: win,
+                    urgent: urg,
+                    screen: s,
+                    workspace: w,
+                },
+            );
+            update_net_wm_state(app, win);
+            update_tab_bar(app, s);
+        }
     }
 
     unsafe {
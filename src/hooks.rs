@@ -0,0 +1,128 @@
+//! User hook scripts ran on WM events
+//!
+//! Executables are looked up in `$XDG_CONFIG_HOME/rtwm/hooks/` (or `~/.config/rtwm/hooks/`)
+//! and named after event they handle:
+//! - `on-window-open`, `on-window-close`, `on-window-focus`, `on-window-title-change`
+//! - `on-workspace-change`
+//! - `on-urgent` (window became urgent)
+//! - `on-layout-change`
+//! - `on-screens-change`
+//!
+//! Context is passed in environment: `RTWM_EVENT`, `RTWM_SCREEN`, `RTWM_WORKSPACE` and, for
+//! window events, `RTWM_WINDOW_ID`, `RTWM_WINDOW_NAME`, `RTWM_CLASS`, `RTWM_INSTANCE`.
+//! `on-layout-change` also gets `RTWM_LAYOUT`, `on-screens-change` gets `RTWM_SCREENS`.
+
+use std::ffi::CString;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use crate::helper::spawn_with_env;
+use crate::loader::config_path;
+use crate::structs::*;
+use crate::utils::*;
+use crate::wrapper::xlib::*;
+
+/// Directory with hook scripts
+pub fn hooks_dir() -> Option<PathBuf> {
+    Some(config_path()?.parent()?.join("hooks"))
+}
+
+/// Name of hook executable for event, `None` if event has no hook
+fn hook_name(event: &WmEvent) -> Option<&'static str> {
+    match event {
+        WmEvent::WindowOpened { .. } => Some("on-window-open"),
+        WmEvent::WindowClosed { .. } => Some("on-window-close"),
+        WmEvent::WindowFocused { .. } => Some("on-window-focus"),
+        WmEvent::WindowTitleChanged { .. } => Some("on-window-title-change"),
+        WmEvent::UrgencyChanged { urgent: true, .. } => Some("on-urgent"),
+        WmEvent::UrgencyChanged { urgent: false, .. } => None,
+        WmEvent::WorkspaceFocused { .. } => Some("on-workspace-change"),
+        WmEvent::LayoutChanged { .. } => Some("on-layout-change"),
+        WmEvent::ScreensChanged { .. } => Some("on-screens-change"),
+    }
+}
+
+/// Environment passed to hook
+fn hook_env(app: &mut Application, event: &WmEvent) -> Vec<(&'static str, String)> {
+    let mut window = |win: u64, name: &str, screen: usize, workspace: usize| {
+        let mut ch = ClassHint::default();
+        get_class_hint(app.core.display, win, &mut ch);
+        vec![
+            ("RTWM_WINDOW_ID", win.to_string()),
+            ("RTWM_WINDOW_NAME", name.to_string()),
+            ("RTWM_CLASS", ch.res_class.unwrap_or_default()),
+            ("RTWM_INSTANCE", ch.res_name.unwrap_or_default()),
+            ("RTWM_SCREEN", screen.to_string()),
+            ("RTWM_WORKSPACE", workspace.to_string()),
+        ]
+    };
+    match event {
+        WmEvent::WindowOpened {
+            window_id,
+            name,
+            screen,
+            workspace,
+        } => window(*window_id, name, *screen, *workspace),
+        WmEvent::WindowClosed {
+            window_id,
+            screen,
+            workspace,
+        } => window(*window_id, "", *screen, *workspace),
+        WmEvent::WindowFocused {
+            window_id,
+            name,
+            screen,
+            workspace,
+        } => window(*window_id, name, *screen, *workspace),
+        WmEvent::WindowTitleChanged {
+            window_id,
+            name,
+            screen,
+            workspace,
+        } => window(*window_id, name, *screen, *workspace),
+        WmEvent::UrgencyChanged {
+            window_id,
+            screen,
+            workspace,
+            ..
+        } => window(*window_id, "", *screen, *workspace),
+        WmEvent::WorkspaceFocused { screen, workspace } => vec![
+            ("RTWM_SCREEN", screen.to_string()),
+            ("RTWM_WORKSPACE", workspace.to_string()),
+        ],
+        WmEvent::LayoutChanged {
+            layout,
+            screen,
+            workspace,
+        } => vec![
+            ("RTWM_LAYOUT", layout.name().to_string()),
+            ("RTWM_SCREEN", screen.to_string()),
+            ("RTWM_WORKSPACE", workspace.to_string()),
+        ],
+        WmEvent::ScreensChanged { screens } => vec![("RTWM_SCREENS", screens.to_string())],
+    }
+}
+
+/// Run hook for event if user has executable for it
+pub fn run_hook(app: &mut Application, event: &WmEvent) {
+    let (dir, name) = match (hooks_dir(), hook_name(event)) {
+        (Some(dir), Some(name)) => (dir, name),
+        _ => return,
+    };
+    let path = dir.join(name);
+    let executable = match std::fs::metadata(&path) {
+        Ok(m) => m.is_file() && m.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    };
+    if !executable {
+        return;
+    }
+    let cmd = match CString::new(path.as_os_str().as_encoded_bytes()) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    log!("|- Running hook {}", path.display());
+    let mut env = hook_env(app, event);
+    env.push(("RTWM_EVENT", name.to_string()));
+    spawn_with_env(app, &[cmd], None, &env);
+}
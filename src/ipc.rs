@@ -0,0 +1,595 @@
+//! Control socket for driving WM from scripts
+//!
+//! Socket is created at `$XDG_RUNTIME_DIR/rtwm-$DISPLAY.sock` and its path is exported
+//! to spawned programs as `RTWM_SOCKET`. Every line sent to it is one command,
+//! written as text (`FocusOnWorkspace 3`, `move-to-screen Next`, `Spawn sh -c 'htop -d 5'`)
+//! or as JSON (`{"FocusOnWorkspace": 3}`, `"ToggleFloat"`). Text is split into words like
+//! shell does, so quoted arguments keep their spaces. Every command gets one line of
+//! JSON in reply: `{"success": true}` or `{"success": false, "error": "..."}`.
+//!
+//! `query state|screens|clients|focus` (or `{"query": "clients"}`) returns part of
+//! WM state described in [`crate::state`] as `{"success": true, "data": ...}`.
+//!
+//! `subscribe [EVENT...]` (or `{"subscribe": ["window-opened"]}`) turns connection into
+//! stream of [`WmEvent`]s, one JSON object per line, e.g.
+//! `{"event":"workspace-focused","screen":0,"workspace":2}`. Without names all events are sent.
+
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use serde_json::json;
+use serde_json::Value;
+
+use crate::actions::run_action;
+use crate::config::NUMBER_OF_DESKTOPS;
+use crate::hooks::run_hook;
+use crate::script::run_script;
+use crate::socket::camel_case;
+use crate::socket::socket_path;
+use crate::state::query;
+use crate::structs::*;
+use crate::utils::*;
+
+/// Longest command line, client sending longer one is dropped
+const MAX_LINE: usize = 64 * 1024;
+/// Most output waiting for client, client not reading it is dropped
+const MAX_OUTPUT: usize = 1024 * 1024;
+
+/// Listening socket and connected clients
+pub struct IpcServer {
+    pub path: PathBuf,
+    pub listener: UnixListener,
+    pub clients: Vec<IpcClient>,
+    /// Id given to next accepted client
+    pub next_id: u64,
+}
+
+/// Connection with partially received command and not yet sent output
+pub struct IpcClient {
+    /// Stays same while clients before it are dropped, unlike index in `clients`
+    pub id: u64,
+    pub stream: UnixStream,
+    pub buffer: Vec<u8>,
+    /// Replies & events socket did not take yet, sent when it becomes writable
+    pub output: Vec<u8>,
+    /// Events client subscribed to, empty list means all events
+    pub subscription: Option<Vec<String>>,
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Create control socket. WM keeps working without it if socket can't be created
+pub fn init_ipc(app: &mut Application) {
+    let path = socket_path();
+    // Remove socket left by previous session
+    let _ = std::fs::remove_file(&path);
+    let listener = match UnixListener::bind(&path) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Failed to create socket {}: {e}", path.display());
+            return;
+        }
+    };
+    if let Err(e) = listener.set_nonblocking(true) {
+        eprintln!("Failed to setup socket {}: {e}", path.display());
+        return;
+    }
+    std::env::set_var("RTWM_SOCKET", &path);
+    log!("|- Listening on {}", path.display());
+    app.core.ipc = Some(IpcServer {
+        path,
+        listener,
+        clients: vec![],
+        next_id: 0,
+    });
+}
+
+/// File descriptors main loop has to wait on besides X connection
+pub fn ipc_fds(app: &Application) -> Vec<i32> {
+    match &app.core.ipc {
+        Some(ipc) => std::iter::once(ipc.listener.as_raw_fd())
+            .chain(ipc.clients.iter().map(|c| c.stream.as_raw_fd()))
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Clients with queued output main loop has to wait to become writable
+pub fn ipc_write_fds(app: &Application) -> Vec<i32> {
+    match &app.core.ipc {
+        Some(ipc) => ipc
+            .clients
+            .iter()
+            .filter(|c| !c.output.is_empty())
+            .map(|c| c.stream.as_raw_fd())
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Accept new connections and run all fully received commands
+///
+/// 1. Accept pending connections
+/// 2. Send queued output, drop clients that stopped reading
+/// 3. Read available data from every client, drop closed ones and ones sending too long lines
+/// 4. Run commands
+/// 5. Queue replies
+///
+/// Commands may emit events dropping subscribers, so clients are looked up by id
+pub fn handle_ipc(app: &mut Application) {
+    let mut commands: Vec<(u64, String)> = vec![];
+    {
+        let ipc = match &mut app.core.ipc {
+            Some(ipc) => ipc,
+            None => return,
+        };
+
+        // 1. Accept
+        while let Ok((stream, _)) = ipc.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                ipc.clients.push(IpcClient {
+                    id: ipc.next_id,
+                    stream,
+                    buffer: vec![],
+                    output: vec![],
+                    subscription: None,
+                });
+                ipc.next_id += 1;
+            }
+        }
+
+        // 2. Send
+        ipc.clients.retain_mut(flush);
+
+        // 3. Read
+        ipc.clients.retain_mut(receive);
+        for client in ipc.clients.iter_mut() {
+            while let Some(end) = client.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = client.buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line).trim().to_string();
+                if !line.is_empty() {
+                    commands.push((client.id, line));
+                }
+            }
+        }
+    }
+
+    for (id, line) in commands {
+        log!("|- Got IPC command `{}`", line);
+        // 4. Run
+        let reply = match parse_subscription(&line) {
+            Some(events) => {
+                if let Some(client) = find_client(app, id) {
+                    client.subscription = Some(events);
+                }
+                json!({ "success": true })
+            }
+            None => match run_command(app, &line) {
+                Ok(Value::Null) => json!({ "success": true }),
+                Ok(data) => json!({ "success": true, "data": data }),
+                Err(e) => json!({ "success": false, "error": e }),
+            },
+        };
+        // 5. Reply
+        if let Some(ipc) = &mut app.core.ipc {
+            ipc.clients
+                .retain_mut(|client| client.id != id || send_line(client, &reply.to_string()));
+        }
+    }
+}
+
+/// Read data available from client. Returns false if client closed connection
+/// or sent line longer than `MAX_LINE`
+fn receive(client: &mut IpcClient) -> bool {
+    let mut chunk = [0u8; 4096];
+    loop {
+        // Rest stays in socket until these lines are handled
+        if client.buffer.len() > MAX_LINE {
+            return true;
+        }
+        match client.stream.read(&mut chunk) {
+            Ok(0) => return false,
+            Ok(n) => {
+                client.buffer.extend_from_slice(&chunk[..n]);
+                let line_start = client
+                    .buffer
+                    .iter()
+                    .rposition(|b| *b == b'\n')
+                    .map_or(0, |i| i + 1);
+                if client.buffer.len() - line_start > MAX_LINE {
+                    eprintln!("IPC client sent line longer than {MAX_LINE} bytes, dropping it");
+                    return false;
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(_) => return false,
+        }
+    }
+}
+
+/// Connected client with given id, `None` if it was dropped meanwhile
+fn find_client(app: &mut Application, id: u64) -> Option<&mut IpcClient> {
+    app.core
+        .ipc
+        .as_mut()?
+        .clients
+        .iter_mut()
+        .find(|c| c.id == id)
+}
+
+/// Run user hook and send event to every subscribed client, dropping ones that stopped reading
+pub fn emit_event(app: &mut Application, event: WmEvent) {
+    log!("|- Event {:?}", event);
+    run_hook(app, &event);
+    let ipc = match &mut app.core.ipc {
+        Some(ipc) => ipc,
+        None => return,
+    };
+    let value = match serde_json::to_value(&event) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    let name = value["event"].as_str().unwrap_or_default();
+    let line = value.to_string();
+    ipc.clients.retain_mut(|client| match &client.subscription {
+        Some(events) if events.is_empty() || events.iter().any(|e| e == name) => {
+            send_line(client, &line)
+        }
+        _ => true,
+    });
+}
+
+/// Queue single line for client and send as much as socket takes without blocking.
+/// Returns false if client has to be dropped
+pub fn send_line(client: &mut IpcClient, line: &str) -> bool {
+    client.output.extend_from_slice(line.as_bytes());
+    client.output.push(b'\n');
+    flush(client)
+}
+
+/// Send queued output until socket is full. Returns false if connection failed
+/// or client is too far behind
+fn flush(client: &mut IpcClient) -> bool {
+    while !client.output.is_empty() {
+        match client.stream.write(&client.output) {
+            Ok(0) => return false,
+            Ok(n) => {
+                client.output.drain(..n);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(_) => return false,
+        }
+    }
+    client.output.len() <= MAX_OUTPUT
+}
+
+/// Run action or answer query. Returns data for queries and `null` for actions
+fn run_command(app: &mut Application, line: &str) -> Result<Value, String> {
+    if let Some(what) = parse_query(line)? {
+        return query(app, &what);
+    }
+
+    let action = parse_command(line)?;
+    match action {
+        ActionResult::FocusOnWorkspace(n) | ActionResult::MoveToWorkspace(n)
+            if n as usize >= NUMBER_OF_DESKTOPS =>
+        {
+            return Err(format!(
+                "workspace {n} does not exist, there are {NUMBER_OF_DESKTOPS} desktops per screen"
+            ));
+        }
+        ActionResult::Spawn(ref cmd) if cmd.is_empty() => {
+            return Err("empty command".to_string());
+        }
+        ActionResult::Script(ref name) => {
+            return run_script(app, name).map(|_| Value::Null);
+        }
+        ActionResult::SetArrangeEngine(ref ae) if app.runtime.layouts.get(ae.name()).is_none() => {
+            return Err(format!(
+                "unknown layout `{}`, available: {}",
+                ae.name(),
+                app.runtime.layouts.names().join(", ")
+            ));
+        }
+        _ => {}
+    }
+    run_action(app, &action);
+    Ok(Value::Null)
+}
+
+/// Returns event names if line is `subscribe ...` or `{"subscribe": [...]}`
+fn parse_subscription(line: &str) -> Option<Vec<String>> {
+    if line.starts_with('{') {
+        let value: Value = serde_json::from_str(line).ok()?;
+        let events = value.get("subscribe")?;
+        return Some(
+            events
+                .as_array()
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|e| e.as_str())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        );
+    }
+    let mut words = line.split_whitespace();
+    match words.next() {
+        Some(w) if w.eq_ignore_ascii_case("subscribe") => {
+            Some(words.map(|e| e.to_lowercase()).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Returns what is queried if line is `query ...` or `{"query": ...}`
+fn parse_query(line: &str) -> Result<Option<String>, String> {
+    if line.starts_with('{') {
+        if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(line) {
+            if let Some(what) = object.get("query") {
+                return match what.as_str() {
+                    Some(what) => Ok(Some(what.to_string())),
+                    None => Err("query must be a string".to_string()),
+                };
+            }
+        }
+        return Ok(None);
+    }
+    let mut words = line.split_whitespace();
+    match words.next() {
+        Some(w) if w.eq_ignore_ascii_case("query") => {
+            Ok(Some(words.next().unwrap_or("state").to_lowercase()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Parse command written either as JSON or as text
+pub fn parse_command(line: &str) -> Result<ActionResult, String> {
+    if line.starts_with('{') || line.starts_with('"') {
+        let action = serde_json::from_str(line).map_err(|e| format!("invalid command: {e}"))?;
+        // `{"SetArrangeEngine": {"Custom": "tiled"}}` selects built-in layout, as text form does
+        return Ok(match action {
+            ActionResult::SetArrangeEngine(ArrangeEngine::Custom(name)) => {
+                ActionResult::SetArrangeEngine(ArrangeEngine::from_name(&name))
+            }
+            action => action,
+        });
+    }
+
+    let mut words = split_words(line)?.into_iter();
+    let name = match words.next() {
+        Some(name) => camel_case(&name),
+        None => return Err("empty command".to_string()),
+    };
+    let args: Vec<String> = words.collect();
+
+    // Try argument as single value first (as is, then as enum variant), then as list (for `Spawn`)
+    let mut candidates = vec![];
+    match args.len() {
+        0 => candidates.push(Value::String(name.clone())),
+        // Layouts are looked up by registry name, so user ones work too
+        1 if name == "SetArrangeEngine" => {
+            return Ok(ActionResult::SetArrangeEngine(ArrangeEngine::from_name(
+                &args[0],
+            )));
+        }
+        1 => {
+            let value = serde_json::from_str(&args[0])
+                .unwrap_or_else(|_| Value::String(args[0].to_string()));
+            candidates.push(json!({ name.clone(): value }));
+            candidates.push(json!({ name.clone(): camel_case(&args[0]) }));
+            candidates.push(json!({ name.clone(): args }));
+        }
+        _ => candidates.push(json!({ name.clone(): args })),
+    }
+    for candidate in candidates {
+        if let Ok(action) = serde_json::from_value(candidate) {
+            return Ok(action);
+        }
+    }
+    Err(format!("unknown command or wrong arguments: `{line}`"))
+}
+
+/// Split text command into words the way shell does: quotes and backslashes keep
+/// spaces inside argument, e.g. `Spawn sh -c 'notify-send hi'`
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\\' => match chars.next() {
+                Some(escaped) => word.get_or_insert_with(String::new).push(escaped),
+                None => return Err("trailing backslash".to_string()),
+            },
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => word.push(escaped),
+                            None => return Err("unterminated quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated quote".to_string()),
+                    }
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_pair() -> (IpcClient, UnixStream) {
+        let (stream, other) = UnixStream::pair().unwrap();
+        stream.set_nonblocking(true).unwrap();
+        let client = IpcClient {
+            id: 0,
+            stream,
+            buffer: vec![],
+            output: vec![],
+            subscription: None,
+        };
+        (client, other)
+    }
+
+    #[test]
+    fn output_is_queued_while_client_does_not_read() {
+        let (mut client, mut other) = client_pair();
+        let line = "x".repeat(1000);
+        // Socket buffer fills up, rest waits in queue instead of blocking
+        while client.output.is_empty() {
+            assert!(send_line(&mut client, &line));
+        }
+        other.set_nonblocking(true).unwrap();
+        let mut received = vec![];
+        let mut chunk = [0u8; 4096];
+        while !client.output.is_empty() {
+            while let Ok(n) = other.read(&mut chunk) {
+                received.extend_from_slice(&chunk[..n]);
+            }
+            assert!(flush(&mut client));
+        }
+        while let Ok(n) = other.read(&mut chunk) {
+            received.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(received.len() % (line.len() + 1), 0);
+        assert!(received
+            .split(|b| *b == b'\n')
+            .all(|l| l.is_empty() || l == line.as_bytes()));
+    }
+
+    #[test]
+    fn client_too_far_behind_is_dropped() {
+        let (mut client, _other) = client_pair();
+        let line = "x".repeat(64 * 1024);
+        let sent = (0..2 * MAX_OUTPUT / line.len()).all(|_| send_line(&mut client, &line));
+        assert!(!sent);
+    }
+
+    #[test]
+    fn endless_line_drops_client() {
+        let (mut client, mut other) = client_pair();
+        other.write_all(b"FocusOnWorkspace 1\nKillClient").unwrap();
+        assert!(receive(&mut client));
+        assert_eq!(client.buffer, b"FocusOnWorkspace 1\nKillClient");
+
+        std::thread::spawn(move || {
+            let _ = other.write_all(&vec![b'x'; 2 * MAX_LINE]);
+        });
+        let mut dropped = false;
+        for _ in 0..1000 {
+            if !receive(&mut client) {
+                dropped = true;
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(dropped);
+    }
+
+    fn spawned(action: ActionResult) -> Vec<String> {
+        match action {
+            ActionResult::Spawn(cmd) => cmd.into_iter().map(|c| c.into_string().unwrap()).collect(),
+            other => panic!("expected Spawn, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn text_spawn_keeps_quoted_arguments() {
+        let action = parse_command(r#"Spawn sh -c 'notify-send hi' "a \"b\"" c\ d"#).unwrap();
+        assert_eq!(
+            spawned(action),
+            ["sh", "-c", "notify-send hi", "a \"b\"", "c d"]
+        );
+        assert_eq!(
+            spawned(parse_command("spawn alacritty").unwrap()),
+            ["alacritty"]
+        );
+    }
+
+    #[test]
+    fn json_spawn_keeps_arguments() {
+        let action = parse_command(r#"{"Spawn": ["sh", "-c", "notify-send hi"]}"#).unwrap();
+        assert_eq!(spawned(action), ["sh", "-c", "notify-send hi"]);
+    }
+
+    #[test]
+    fn unbalanced_quotes_are_errors() {
+        assert!(parse_command("Spawn sh -c 'notify-send hi").is_err());
+        assert!(parse_command(r#"Spawn "sh"#).is_err());
+        assert!(parse_command(r"Spawn sh\").is_err());
+        assert!(parse_command("  ").is_err());
+    }
+
+    #[test]
+    fn text_and_json_forms_match() {
+        for (text, json) in [
+            ("FocusOnWorkspace 3", r#"{"FocusOnWorkspace": 3}"#),
+            ("move-to-screen next", r#"{"MoveToScreen": "Next"}"#),
+            ("update-master-width 0.05", r#"{"UpdateMasterWidth": 0.05}"#),
+            ("toggle-float", r#""ToggleFloat""#),
+            ("Script 'my script'", r#"{"Script": "my script"}"#),
+        ] {
+            assert_eq!(
+                format!("{:?}", parse_command(text).unwrap()),
+                format!("{:?}", parse_command(json).unwrap()),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn layouts_are_resolved_by_name() {
+        for line in [
+            "set-arrange-engine centered-master",
+            r#"{"SetArrangeEngine": "CenteredMaster"}"#,
+            r#"{"SetArrangeEngine": {"Custom": "centered-master"}}"#,
+        ] {
+            assert!(
+                matches!(
+                    parse_command(line),
+                    Ok(ActionResult::SetArrangeEngine(
+                        ArrangeEngine::CenteredMaster
+                    ))
+                ),
+                "{line}"
+            );
+        }
+        assert!(matches!(
+            parse_command("set-arrange-engine my-layout"),
+            Ok(ActionResult::SetArrangeEngine(ArrangeEngine::Custom(name))) if name == "my-layout"
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+//! Layout interface used by [`crate::arrange::arrange_workspace`]
+//!
+//! Layout is pure function from usable area, settings and tileable clients to geometry of
+//! every client, so it does not need X server or [`crate::structs::Application`] to run.
+//! Built-in layouts are registered under names of [`ArrangeEngine`] variants in kebab-case
+//! (`tiled`, `centered-master`, ...). Own layouts are returned from `config::layouts()` and
+//! selected with `ArrangeEngine::Custom("name")`.
+
+use std::fmt;
+
+use crate::structs::*;
+
+/// Rectangle in root window coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Area {
+    /// Area with `by` pixels taken from every side, negative values grow it
+    pub fn shrink(&self, by: i32) -> Area {
+        Area {
+            x: self.x + by,
+            y: self.y + by,
+            w: self.w - 2 * by,
+            h: self.h - 2 * by,
+        }
+    }
+}
+
+/// Position, size & border width of client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+    pub border: u32,
+}
+
+impl Geometry {
+    /// Geometry filling area, with border drawn inside it
+    pub fn inside(area: Area, border: u32) -> Geometry {
+        Geometry {
+            x: area.x,
+            y: area.y,
+            w: (area.w - 2 * border as i32).max(1) as u32,
+            h: (area.h - 2 * border as i32).max(1) as u32,
+            border,
+        }
+    }
+}
+
+/// Configuration & workspace settings layout may use
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutSettings {
+    /// Gap between clients
+    pub gap: i32,
+    /// Gap between clients and edge of area
+    pub outer_gap: i32,
+    /// No gaps around single visible client
+    pub smart_gaps: bool,
+    /// No border around single visible client
+    pub smart_borders: bool,
+    pub border: u32,
+    pub master_width: f64,
+    pub master_capacity: i64,
+    pub three_column_master: MasterPosition,
+    /// Height of `Tabbed` tab strip
+    pub tab_bar_height: i32,
+}
+
+pub trait Layout {
+    /// Name layout is registered with
+    fn name(&self) -> &str;
+
+    /// Geometry for every client, `None` leaves client as is.
+    /// Clients are in stacking order, newest first
+    fn arrange(
+        &self,
+        area: Area,
+        settings: &LayoutSettings,
+        clients: &[&Client],
+    ) -> Vec<Option<Geometry>>;
+
+    /// Whether `UpdateMasterWidth` has effect
+    fn uses_master_width(&self) -> bool {
+        false
+    }
+
+    /// Whether `UpdateMasterCapacity` has effect
+    fn uses_master_capacity(&self) -> bool {
+        false
+    }
+
+    /// Whether `UpdateClientWeight` has effect
+    fn uses_client_weight(&self) -> bool {
+        false
+    }
+
+    /// Whether `PopPushStack` & `RotateStack` have effect
+    fn reorderable(&self) -> bool {
+        true
+    }
+}
+
+/// Layouts available by name
+pub struct LayoutRegistry {
+    layouts: Vec<Box<dyn Layout>>,
+}
+
+impl LayoutRegistry {
+    /// Registry with built-in layouts
+    pub fn new() -> LayoutRegistry {
+        let mut registry = LayoutRegistry { layouts: vec![] };
+        for layout in crate::arrange::builtin_layouts() {
+            registry.register(layout);
+        }
+        registry
+    }
+
+    /// Add layout, replacing one with same name
+    pub fn register(&mut self, layout: Box<dyn Layout>) {
+        self.layouts.retain(|l| l.name() != layout.name());
+        self.layouts.push(layout);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Layout> {
+        self.layouts
+            .iter()
+            .find(|l| l.name() == name)
+            .map(|l| l.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.layouts.iter().map(|l| l.name()).collect()
+    }
+}
+
+/// Registry with built-in layouts & ones from `config::layouts()`
+pub fn load_layouts() -> LayoutRegistry {
+    let mut registry = LayoutRegistry::new();
+    for layout in crate::config::layouts() {
+        registry.register(layout);
+    }
+    registry
+}
+
+impl Default for LayoutRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for LayoutRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl ArrangeEngine {
+    /// Name of layout in [`LayoutRegistry`]
+    pub fn name(&self) -> &str {
+        match self {
+            ArrangeEngine::Tiled => "tiled",
+            ArrangeEngine::Mono => "mono",
+            ArrangeEngine::Grid => "grid",
+            ArrangeEngine::CenteredMaster => "centered-master",
+            ArrangeEngine::BottomStack => "bottom-stack",
+            ArrangeEngine::Spiral => "spiral",
+            ArrangeEngine::Dwindle => "dwindle",
+            ArrangeEngine::Tabbed => "tabbed",
+            ArrangeEngine::ThreeColumn => "three-column",
+            ArrangeEngine::Custom(name) => name,
+        }
+    }
+
+    /// Layout by registry name, case and dashes are ignored for built-in ones.
+    /// Unknown names are [`ArrangeEngine::Custom`]
+    pub fn from_name(name: &str) -> ArrangeEngine {
+        let builtin = [
+            ArrangeEngine::Tiled,
+            ArrangeEngine::Mono,
+            ArrangeEngine::Grid,
+            ArrangeEngine::CenteredMaster,
+            ArrangeEngine::BottomStack,
+            ArrangeEngine::Spiral,
+            ArrangeEngine::Dwindle,
+            ArrangeEngine::Tabbed,
+            ArrangeEngine::ThreeColumn,
+        ];
+        let normalize = |s: &str| s.replace(['-', '_'], "").to_lowercase();
+        builtin
+            .into_iter()
+            .find(|ae| normalize(ae.name()) == normalize(name))
+            .unwrap_or_else(|| ArrangeEngine::Custom(name.to_string()))
+    }
+}
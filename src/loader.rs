@@ -0,0 +1,414 @@
+//! Loading user configuration from TOML file at runtime
+//!
+//! File is looked up at `$XDG_CONFIG_HOME/rtwm/config.toml` (or `~/.config/rtwm/config.toml`).
+//! Every field is optional, missing ones are taken from compiled-in [`config()`].
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::config::config;
+use crate::structs::*;
+use crate::wrapper::xlib::keysym_to_string;
+use crate::wrapper::xlib::string_to_keysym;
+
+use x11::xlib::ControlMask;
+use x11::xlib::LockMask;
+use x11::xlib::Mod1Mask;
+use x11::xlib::Mod2Mask;
+use x11::xlib::Mod3Mask;
+use x11::xlib::Mod4Mask;
+use x11::xlib::Mod5Mask;
+use x11::xlib::ShiftMask;
+
+/// Layout of config file, mirrors [`Configuration`]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    key_actions: Option<Vec<KeyActionFile>>,
+    gap_width: Option<usize>,
+    outer_gap_width: Option<usize>,
+    smart_gaps: Option<bool>,
+    smart_borders: Option<bool>,
+    border_size: Option<usize>,
+    normal_border_color: Option<Color>,
+    active_border_color: Option<Color>,
+    urgent_border_color: Option<Color>,
+    three_column_master: Option<MasterPosition>,
+    layout_cycle: Option<Vec<ArrangeEngine>>,
+    tab_bar: Option<TabBarFile>,
+    desktops: Option<DesktopsFile>,
+    autostart: Option<Vec<AutostartRuleCMD>>,
+    placements: Option<Vec<PlacementRule>>,
+}
+
+/// Key binding written as `key = "Mod4+Shift+Return"`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyActionFile {
+    key: String,
+    result: ActionResult,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TabBarFile {
+    height: Option<i32>,
+    font: Option<String>,
+    text_color: Option<Color>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DesktopsFile {
+    keysyms: Option<Vec<String>>,
+    names: Option<Vec<Vec<String>>>,
+    splits: Option<Vec<Vec<f64>>>,
+    layouts: Option<Vec<Vec<ArrangeEngine>>>,
+    master_capacities: Option<Vec<Vec<i64>>>,
+    gaps: Option<Vec<Vec<GapFile>>>,
+    focus_modifier: Option<String>,
+    move_modifier: Option<String>,
+}
+
+/// Workspace gap, `"default"` keeps `gap_width` & `outer_gap_width`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GapFile {
+    Width(i64),
+    Keyword(String),
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    /// Parse `#rrggbb` or `#aarrggbb`
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        let n = match u32::from_str_radix(hex, 16) {
+            Ok(n) if hex.len() == 6 || hex.len() == 8 => n,
+            _ => {
+                return Err(format!(
+                    "invalid color `{value}`, expected #rrggbb or #aarrggbb"
+                ))
+            }
+        };
+        Ok(Color {
+            alpha: if hex.len() == 8 { (n >> 24) as u8 } else { 255 },
+            red: (n >> 16) as u8,
+            green: (n >> 8) as u8,
+            blue: n as u8,
+        })
+    }
+}
+
+/// Path to config file if home directory can be found
+pub fn config_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("rtwm").join("config.toml"))
+}
+
+/// Load configuration from config file, fall back to compiled-in one if no file exists
+///
+/// Errors in existing file are printed and compiled-in config is used instead
+pub fn load_config() -> Configuration {
+    match config_path() {
+        Some(path) if path.exists() => match read_config_file(&path) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("{e}");
+                eprintln!("Using built-in configuration");
+                config()
+            }
+        },
+        _ => config(),
+    }
+}
+
+/// Read & parse config file into [`Configuration`]
+pub fn read_config_file(path: &Path) -> Result<Configuration, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("{}: failed to read: {e}", path.display()))?;
+    parse_config(&text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Parse config from TOML text
+///
+/// Fields not present in text are taken from compiled-in [`config()`].
+/// If `key_actions` are set, workspace bindings are generated from `desktops.keysyms`
+pub fn parse_config(text: &str) -> Result<Configuration, String> {
+    let file: ConfigFile = toml::from_str(text).map_err(|e| e.to_string())?;
+    let mut c = config();
+
+    if let Some(v) = file.gap_width {
+        c.gap_width = v;
+        // Gap used to be same for both
+        c.outer_gap_width = v;
+    }
+    if let Some(v) = file.outer_gap_width {
+        c.outer_gap_width = v;
+    }
+    if let Some(v) = file.smart_gaps {
+        c.smart_gaps = v;
+    }
+    if let Some(v) = file.smart_borders {
+        c.smart_borders = v;
+    }
+    if let Some(v) = file.border_size {
+        c.border_size = v;
+    }
+    if let Some(v) = file.normal_border_color {
+        c.normal_border_color = v;
+    }
+    if let Some(v) = file.active_border_color {
+        c.active_border_color = v;
+    }
+    if let Some(v) = file.urgent_border_color {
+        c.urgent_border_color = v;
+    }
+    if let Some(v) = file.three_column_master {
+        c.three_column_master = v;
+    }
+    if let Some(v) = file.layout_cycle {
+        c.layout_cycle = v;
+    }
+    if let Some(tab_bar) = file.tab_bar {
+        if let Some(v) = tab_bar.height {
+            c.tab_bar.height = v;
+        }
+        if let Some(v) = tab_bar.font {
+            c.tab_bar.font = v;
+        }
+        if let Some(v) = tab_bar.text_color {
+            c.tab_bar.text_color = v;
+        }
+    }
+    if let Some(v) = file.autostart {
+        c.autostart = v;
+    }
+    if let Some(v) = file.placements {
+        c.placements = v;
+    }
+
+    let mut focus_modifier = Mod4Mask;
+    let mut move_modifier = Mod4Mask | ShiftMask;
+    if let Some(desktops) = file.desktops {
+        if let Some(keysyms) = desktops.keysyms {
+            c.desktops.keysyms = vec![];
+            for (i, name) in keysyms.iter().enumerate() {
+                match string_to_keysym(name) {
+                    0 => return Err(format!("desktops.keysyms[{i}]: unknown keysym `{name}`")),
+                    k => c.desktops.keysyms.push(k),
+                }
+            }
+        }
+        if let Some(names) = desktops.names {
+            c.desktops.names = names;
+        }
+        if let Some(splits) = desktops.splits {
+            c.desktops.splits = splits;
+        }
+        if let Some(layouts) = desktops.layouts {
+            c.desktops.layouts = layouts;
+        }
+        if let Some(capacities) = desktops.master_capacities {
+            c.desktops.master_capacities = capacities;
+        }
+        if let Some(gaps) = desktops.gaps {
+            c.desktops.gaps = vec![];
+            for (s, gaps) in gaps.into_iter().enumerate() {
+                let mut screen = vec![];
+                for (w, gap) in gaps.into_iter().enumerate() {
+                    screen.push(match gap {
+                        GapFile::Width(n) if n >= 0 => Some(n as usize),
+                        GapFile::Keyword(k) if k == "default" => None,
+                        _ => {
+                            return Err(format!(
+                                "desktops.gaps[{s}][{w}]: expected gap width or \"default\""
+                            ))
+                        }
+                    });
+                }
+                c.desktops.gaps.push(screen);
+            }
+        }
+        if let Some(m) = desktops.focus_modifier {
+            focus_modifier =
+                parse_modifiers(&m).map_err(|e| format!("desktops.focus_modifier: {e}"))?;
+        }
+        if let Some(m) = desktops.move_modifier {
+            move_modifier =
+                parse_modifiers(&m).map_err(|e| format!("desktops.move_modifier: {e}"))?;
+        }
+    }
+
+    if let Some(key_actions) = file.key_actions {
+        c.key_actions = vec![];
+        for (i, action) in key_actions.into_iter().enumerate() {
+            let (modifier, keysym) =
+                parse_key(&action.key).map_err(|e| format!("key_actions[{i}]: {e}"))?;
+            c.key_actions.push(KeyAction {
+                keysym,
+                modifier,
+                result: action.result,
+            });
+        }
+        for (i, k) in c.desktops.keysyms.iter().enumerate() {
+            c.key_actions.push(KeyAction {
+                modifier: focus_modifier,
+                keysym: *k,
+                result: ActionResult::FocusOnWorkspace(i as u64),
+            });
+            c.key_actions.push(KeyAction {
+                modifier: move_modifier,
+                keysym: *k,
+                result: ActionResult::MoveToWorkspace(i as u64),
+            });
+        }
+    }
+
+    Ok(c)
+}
+
+/// Convert string like `Mod4+Shift+Return` into modifier mask and keysym.
+/// Plus key itself is written as `Mod4++` or `Mod4+plus`
+pub fn parse_key(s: &str) -> Result<(u32, u32), String> {
+    let (modifiers, name) = match s.strip_suffix("++") {
+        Some(m) => (parse_modifiers(m)?, "plus"),
+        None if s.trim() == "+" => (0, "plus"),
+        None => match s.rsplit_once('+') {
+            Some((m, n)) => (parse_modifiers(m)?, n),
+            None => (0, s),
+        },
+    };
+    match string_to_keysym(name.trim()) {
+        0 => Err(format!("unknown keysym `{}` in `{s}`", name.trim())),
+        keysym => Ok((modifiers, keysym)),
+    }
+}
+
+/// Convert string like `Mod4+Shift` into modifier mask
+pub fn parse_modifiers(s: &str) -> Result<u32, String> {
+    let mut mask = 0;
+    for m in s.split('+').map(|m| m.trim()).filter(|m| !m.is_empty()) {
+        mask |= match m.to_lowercase().as_str() {
+            "shift" => ShiftMask,
+            "lock" => LockMask,
+            "control" | "ctrl" => ControlMask,
+            "mod1" | "alt" => Mod1Mask,
+            "mod2" => Mod2Mask,
+            "mod3" => Mod3Mask,
+            "mod4" | "super" => Mod4Mask,
+            "mod5" => Mod5Mask,
+            _ => return Err(format!("unknown modifier `{m}`")),
+        };
+    }
+    Ok(mask)
+}
+
+/// Convert modifier mask and keysym back into string like `Mod4+Shift+Return`
+pub fn key_to_string(modifier: u32, keysym: u32) -> String {
+    let mut parts: Vec<String> = [
+        (Mod1Mask, "Mod1"),
+        (Mod2Mask, "Mod2"),
+        (Mod3Mask, "Mod3"),
+        (Mod4Mask, "Mod4"),
+        (Mod5Mask, "Mod5"),
+        (ControlMask, "Control"),
+        (ShiftMask, "Shift"),
+        (LockMask, "Lock"),
+    ]
+    .iter()
+    .filter(|(mask, _)| modifier & mask != 0)
+    .map(|(_, name)| name.to_string())
+    .collect();
+    parts.push(keysym_to_string(keysym).unwrap_or_else(|| format!("{keysym:#x}")));
+    parts.join("+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_accept_aliases_in_any_case() {
+        assert_eq!(parse_modifiers("Mod4+Shift"), Ok(Mod4Mask | ShiftMask));
+        assert_eq!(parse_modifiers("super+SHIFT"), Ok(Mod4Mask | ShiftMask));
+        assert_eq!(parse_modifiers("Ctrl + alt"), Ok(ControlMask | Mod1Mask));
+        assert_eq!(parse_modifiers("control+Mod1"), Ok(ControlMask | Mod1Mask));
+        assert_eq!(parse_modifiers(""), Ok(0));
+        assert_eq!(
+            parse_modifiers("Mod4+Hyper"),
+            Err("unknown modifier `Hyper`".to_string())
+        );
+    }
+
+    #[test]
+    fn keys_are_parsed() {
+        let keysym = |name| string_to_keysym(name);
+        assert_eq!(
+            parse_key("Mod4+Shift+Return"),
+            Ok((Mod4Mask | ShiftMask, keysym("Return")))
+        );
+        assert_eq!(parse_key("F1"), Ok((0, keysym("F1"))));
+        assert_eq!(parse_key("Mod4++"), Ok((Mod4Mask, keysym("plus"))));
+        assert_eq!(parse_key("Mod4+plus"), Ok((Mod4Mask, keysym("plus"))));
+        assert_eq!(parse_key("+"), Ok((0, keysym("plus"))));
+        assert_eq!(
+            parse_key("Mod4+Retrun"),
+            Err("unknown keysym `Retrun` in `Mod4+Retrun`".to_string())
+        );
+        assert_eq!(
+            parse_key("Hyper+Return"),
+            Err("unknown modifier `Hyper`".to_string())
+        );
+        assert!(parse_key("Mod4+").is_err());
+    }
+
+    #[test]
+    fn colors_are_parsed() {
+        let color =
+            |s: &str| Color::try_from(s.to_string()).map(|c| (c.alpha, c.red, c.green, c.blue));
+        assert_eq!(color("#102030"), Ok((0xff, 0x10, 0x20, 0x30)));
+        assert_eq!(color("80102030"), Ok((0x80, 0x10, 0x20, 0x30)));
+        for bad in ["#12345", "#1234567", "#gg0000", "", "#", "red"] {
+            assert!(color(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn bad_config_is_rejected() {
+        assert!(parse_config("gap_width = -1").is_err());
+        assert!(parse_config("unknown_field = 1").is_err());
+        assert!(parse_config(r##"active_border_color = "#zzzzzz""##).is_err());
+        let error = parse_config(r#"key_actions = [{ key = "Mod4+Nope", result = "KillClient" }]"#)
+            .err()
+            .unwrap();
+        assert!(
+            error.starts_with("key_actions[0]: unknown keysym `Nope`"),
+            "{error}"
+        );
+        assert_eq!(
+            parse_config("[desktops]\ngaps = [[0, -1]]").err(),
+            Some("desktops.gaps[0][1]: expected gap width or \"default\"".to_string())
+        );
+        assert!(parse_config("[desktops]\ngaps = [[\"none\"]]").is_err());
+    }
+
+    #[test]
+    fn default_gap_keeps_global_one() {
+        let config = parse_config("[desktops]\ngaps = [[\"default\", 0, 8]]").unwrap();
+        assert_eq!(config.desktops.gaps, [[None, Some(0), Some(8)]]);
+    }
+
+    #[test]
+    fn example_config_parses() {
+        let config = parse_config(include_str!("../config-example.toml")).unwrap();
+        assert!(!config.key_actions.is_empty());
+        assert_eq!(config.tab_bar.height, 20);
+    }
+}
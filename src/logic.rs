@@ -1,16 +1,16 @@
 //! Main windows manager logic processed as response to events
 
-use std::process::exit;
-
 use crate::config::NUMBER_OF_DESKTOPS;
 use crate::helper::*;
+use crate::ipc::emit_event;
 use crate::structs::*;
+use crate::tabs::update_tab_bar;
 use crate::utils::*;
 use crate::wrapper::xinerama::xinerama_query_screens;
 use crate::wrapper::xlib::*;
+use crate::wrapper::xrandr::*;
 
 use x11::xinerama::XineramaIsActive;
-use x11::xinerama::XineramaScreenInfo;
 use x11::xlib::AnyButton;
 use x11::xlib::AnyModifier;
 use x11::xlib::Button1;
@@ -69,6 +69,50 @@ pub fn shift_current_client(app: &mut Application, screen: usize, workspace: usi
     }
 }
 
+/// Move sticky clients of screen from workspace being hidden to one being shown
+///
+/// 1. Split sticky clients out keeping their order
+/// 2. Keep current client of both workspaces pointing at same window
+/// 3. Update desktops of moved clients
+pub fn move_sticky_clients(app: &mut Application, screen: usize, from: usize, to: usize) {
+    if from == to {
+        return;
+    }
+    // 1. Split
+    let workspaces = &mut app.runtime.screens[screen].workspaces;
+    let current = workspaces[from]
+        .current_client
+        .and_then(|c| workspaces[from].clients.get(c))
+        .map(|c| c.window_id);
+    let (sticky, rest): (Vec<Client>, Vec<Client>) = std::mem::take(&mut workspaces[from].clients)
+        .into_iter()
+        .partition(|c| c.sticky);
+    workspaces[from].clients = rest;
+    if sticky.is_empty() {
+        return;
+    }
+    let windows: Vec<u64> = sticky.iter().map(|c| c.window_id).collect();
+
+    // 2. Trackers
+    let source = &mut workspaces[from];
+    source.current_client =
+        match current.and_then(|w| source.clients.iter().position(|c| c.window_id == w)) {
+            Some(index) => Some(index),
+            None => source.clients.len().checked_sub(1),
+        };
+    let target = &mut workspaces[to];
+    target.clients.extend(sticky);
+    if target.current_client.is_none() {
+        target.current_client = Some(target.clients.len() - 1);
+    }
+
+    // 3. Desktops
+    let desktop = (to + screen * NUMBER_OF_DESKTOPS) as u64;
+    for win in windows {
+        update_client_desktop(app, win, desktop);
+    }
+}
+
 /// Get name from x server for specified window and undate it in struct
 /// 1. Get name property
 /// 2. Set window name if window is managed
@@ -81,7 +125,20 @@ pub fn update_client_name(app: &mut Application, win: u64) {
 
     // 2. Set
     if let Some((s, w, c)) = find_window_indexes(app, win) {
-        app.runtime.screens[s].workspaces[w].clients[c].window_name = name;
+        let client = &mut app.runtime.screens[s].workspaces[w].clients[c];
+        if client.window_name != name {
+            client.window_name = name.clone();
+            emit_event(
+                app,
+                WmEvent::WindowTitleChanged {
+                    window_id: win,
+                    name,
+                    screen: s,
+                    workspace: w,
+                },
+            );
+            update_tab_bar(app, s);
+        }
     }
 }
 
@@ -100,6 +157,10 @@ pub fn get_client_name(app: &mut Application, win: u64) -> String {
 }
 
 pub fn focus(app: &mut Application, win: u64) {
+    // Notifications, menus & desktop windows leave focus where it was
+    if find_window_indexes(app, win).is_some() && !accepts_focus(app, win) {
+        return;
+    }
     set_urgent(app, win, false);
     set_window_border(
         app.core.display,
@@ -128,6 +189,22 @@ pub fn focus(app: &mut Application, win: u64) {
         &w as *const usize as *mut usize as *mut u8,
         1,
     );
+
+    if let Some((s, w, c)) = find_window_indexes(app, win) {
+        let name = app.runtime.screens[s].workspaces[w].clients[c]
+            .window_name
+            .clone();
+        emit_event(
+            app,
+            WmEvent::WindowFocused {
+                window_id: win,
+                name,
+                screen: s,
+                workspace: w,
+            },
+        );
+        update_tab_bar(app, s);
+    }
 }
 
 pub fn unfocus(app: &mut Application, win: u64) {
@@ -150,83 +227,271 @@ pub fn update_trackers(app: &mut Application, win: u64) {
     };
 }
 
-/// Update screens
+/// Monitors to put screens on
 ///
-/// 1. Get screens from xinerama
-/// 2. Add more screens if amount of new screens is larger than amount of existing screens
-/// 3. Init newly created screens
-/// 4. Move everything from exceeding screens and delete them
-pub fn update_screens(app: &mut Application) {
-    // 1. Get screens
-    let n = app.runtime.screens.len();
-
-    if unsafe { XineramaIsActive(app.core.display) } == 0 {
-        eprintln!("Running without xinerama is not supported");
-        exit(1);
+/// 1. XRandR 1.5 monitors, primary first, others left to right
+/// 2. Xinerama screens if XRandR is not available
+/// 3. Whole root window if neither is available
+fn query_monitors(app: &mut Application) -> Vec<MonitorInfo> {
+    // 1. XRandR
+    if let Some(mut monitors) = xrandr_get_monitors(app.core.display, app.core.root_win) {
+        monitors.sort_by_key(|m| (!m.primary, m.x, m.y));
+        return monitors;
     }
 
-    let screens = match xinerama_query_screens(app.core.display) {
-        Some(s) => s,
-        None => {
-            eprintln!("xinerama error");
-            exit(1);
+    // 2. Xinerama
+    if unsafe { XineramaIsActive(app.core.display) } != 0 {
+        if let Some(screens) = xinerama_query_screens(app.core.display) {
+            let mut monitors: Vec<MonitorInfo> = vec![];
+            for screen in screens {
+                if !monitors
+                    .iter()
+                    .any(|m| m.x == screen.x_org as i32 && m.y == screen.y_org as i32)
+                {
+                    monitors.push(MonitorInfo {
+                        name: format!("XINERAMA-{}", screen.screen_number),
+                        primary: monitors.is_empty(),
+                        x: screen.x_org as i32,
+                        y: screen.y_org as i32,
+                        width: screen.width as i32,
+                        height: screen.height as i32,
+                    });
+                }
+            }
+            if !monitors.is_empty() {
+                return monitors;
+            }
         }
+    }
+
+    // 3. Root window
+    log!("|- Neither XRandR nor Xinerama available, using single screen");
+    let (width, height) = display_size(app.core.display);
+    vec![MonitorInfo {
+        name: "default".to_string(),
+        primary: true,
+        x: 0,
+        y: 0,
+        width,
+        height,
+    }]
+}
+
+/// Remove client from its workspace keeping workspace tracker valid
+fn take_client(app: &mut Application, win: u64) -> Option<(usize, Client)> {
+    let (s, w, c) = find_window_indexes(app, win)?;
+    let workspace = &mut app.runtime.screens[s].workspaces[w];
+    let client = workspace.clients.remove(c);
+    workspace.current_client = match workspace.current_client {
+        _ if workspace.clients.is_empty() => None,
+        Some(cc) if cc > c => Some(cc - 1),
+        Some(cc) => Some(cc.min(workspace.clients.len() - 1)),
+        None => None,
     };
+    Some((s, client))
+}
 
-    let screens = {
-        let mut tmp: Vec<XineramaScreenInfo> = vec![];
-        for screen in screens {
-            if tmp
-                .iter()
-                .filter(|ts| ts.x_org == screen.x_org && ts.y_org == screen.y_org)
-                .count()
-                == 0
-            {
-                tmp.push(screen);
-            }
+/// Keep floating client at same place relative to origin of screen it is moved to
+fn translate_floating(client: &mut Client, from: (i64, i64), to: (i64, i64)) {
+    if client.floating {
+        client.x = client.x - from.0 as i32 + to.0 as i32;
+        client.y = client.y - from.1 as i32 + to.1 as i32;
+        if let Some((x, _)) = &mut client.unmaximized_horz {
+            *x = *x - from.0 as i32 + to.0 as i32;
         }
-        tmp
-    };
+        if let Some((y, _)) = &mut client.unmaximized_vert {
+            *y = *y - from.1 as i32 + to.1 as i32;
+        }
+    }
+}
+
+/// Point trackers of workspaces at existing clients after moving clients around
+fn clamp_current_clients(screen: &mut Screen) {
+    for workspace in &mut screen.workspaces {
+        workspace.current_client = match workspace.current_client {
+            _ if workspace.clients.is_empty() => None,
+            Some(cc) => Some(cc.min(workspace.clients.len() - 1)),
+            None => Some(0),
+        };
+    }
+}
 
-    let screens_amount = screens.len();
+/// Update screens
+///
+/// 1. Get monitors
+/// 2. Keep screens of outputs that are still connected
+/// 3. Restore screens of outputs that were connected before, create new ones for others
+/// 4. Init geometry of screens
+/// 5. Detach screens of disconnected outputs, show their clients on first screen
+/// 6. Move windows of restored screens back
+/// 7. Update desktops of clients and trackers
+pub fn update_screens(app: &mut Application) {
+    // 1. Get monitors
+    let monitors = query_monitors(app);
+    let screens_amount = monitors.len();
+    let current_output = app
+        .runtime
+        .screens
+        .get(app.runtime.current_screen)
+        .map(|s| s.output.clone());
+
+    // 2. Keep connected
+    let mut old: Vec<Option<Screen>> = std::mem::take(&mut app.runtime.screens)
+        .into_iter()
+        .map(Some)
+        .collect();
+    let mut screens: Vec<Option<Screen>> = monitors
+        .iter()
+        .map(|m| {
+            old.iter_mut()
+                .find(|s| matches!(s, Some(s) if s.output == m.name))
+                .and_then(|s| s.take())
+        })
+        .collect();
 
-    // 2. Add new screens
-    for _ in n..screens_amount {
-        app.runtime.screens.push(Screen {
+    // 3. Restore & create
+    let mut restored: Vec<(usize, Vec<Vec<u64>>)> = vec![];
+    for (index, screen) in screens.iter_mut().enumerate() {
+        if screen.is_some() {
+            continue;
+        }
+        let mut new_screen = Screen {
             number: 0,
+            output: String::new(),
             x: 0,
             y: 0,
             width: 0,
             height: 0,
-            workspaces: vec![],
+            workspaces: create_workspaces(&app.config, index),
             current_workspace: 0,
             bar_offsets: BarOffsets::default(),
-        })
+        };
+        let detached = &mut app.runtime.detached_screens;
+        if let Some(d) = detached
+            .iter()
+            .position(|d| d.output == monitors[index].name)
+        {
+            let d = detached.remove(d);
+            log!("   |- Restoring workspaces of `{}`", d.output);
+            new_screen.workspaces = d.workspaces;
+            new_screen.current_workspace = d.current_workspace;
+            restored.push((index, d.windows));
+        }
+        *screen = Some(new_screen);
+    }
+    app.runtime.screens = screens.into_iter().flatten().collect();
+
+    // 4. Init screens
+    for (index, monitor) in monitors.iter().enumerate() {
+        log!("   |- Screen {}: `{}` {:?}", index, monitor.name, monitor);
+        let screen = &mut app.runtime.screens[index];
+        screen.number = index as i64;
+        screen.output = monitor.name.clone();
+        screen.x = monitor.x as i64;
+        screen.y = monitor.y as i64;
+        screen.width = monitor.width as i64;
+        screen.height = monitor.height as i64;
     }
 
-    // 3. Init screens
-    for (index, screen) in screens.iter().enumerate() {
-        app.runtime.screens[index].number = screen.screen_number as i64;
-        app.runtime.screens[index].x = screen.x_org as i64;
-        app.runtime.screens[index].y = screen.y_org as i64;
-        app.runtime.screens[index].width = screen.width as i64;
-        app.runtime.screens[index].height = screen.height as i64;
+    // 5. Detach removed screens
+    for removed_screen in old.into_iter().flatten() {
+        log!("   |- Detaching workspaces of `{}`", removed_screen.output);
+        let from = (removed_screen.x, removed_screen.y);
+        let to = (app.runtime.screens[0].x, app.runtime.screens[0].y);
+        let mut workspaces = vec![];
+        let mut windows = vec![];
+        for (index, mut workspace) in removed_screen.workspaces.into_iter().enumerate() {
+            windows.push(workspace.clients.iter().map(|c| c.window_id).collect());
+            for mut client in workspace.clients.drain(..) {
+                translate_floating(&mut client, from, to);
+                app.runtime.screens[0].workspaces[index]
+                    .clients
+                    .push(client);
+            }
+            workspaces.push(workspace);
+        }
+        let detached = &mut app.runtime.detached_screens;
+        detached.retain(|d| d.output != removed_screen.output);
+        detached.push(DetachedScreen {
+            output: removed_screen.output,
+            current_workspace: removed_screen.current_workspace,
+            workspaces,
+            windows,
+        });
     }
 
-    // 4. Move & delete removed screens
-    for _ in screens_amount..n {
-        if let Some(removed_screen) = app.runtime.screens.pop() {
-            let removed_workspaces = removed_screen.workspaces;
-            for (index, workspace) in removed_workspaces.into_iter().enumerate() {
-                for client in workspace.clients {
-                    update_client_desktop(app, client.window_id, index as u64);
-                    app.runtime.screens[0].workspaces[index]
-                        .clients
-                        .push(client);
+    // 6. Move windows back
+    for (index, windows) in restored {
+        for (workspace, windows) in windows.into_iter().enumerate() {
+            for win in windows {
+                if let Some((from, mut client)) = take_client(app, win) {
+                    let screens = &mut app.runtime.screens;
+                    let (from, to) = (&screens[from], &screens[index]);
+                    translate_floating(&mut client, (from.x, from.y), (to.x, to.y));
+                    if let Some(w) = screens[index].workspaces.get_mut(workspace) {
+                        w.clients.push(client);
+                    }
                 }
             }
         }
+        clamp_current_clients(&mut app.runtime.screens[index]);
     }
+    clamp_current_clients(&mut app.runtime.screens[0]);
+
+    // 7. Update desktops & trackers
+    for s in 0..app.runtime.screens.len() {
+        for w in 0..app.runtime.screens[s].workspaces.len() {
+            for c in 0..app.runtime.screens[s].workspaces[w].clients.len() {
+                let win = app.runtime.screens[s].workspaces[w].clients[c].window_id;
+                update_client_desktop(app, win, (s * NUMBER_OF_DESKTOPS + w) as u64);
+            }
+        }
+    }
+    let rt = &mut app.runtime;
+    rt.current_screen = rt
+        .screens
+        .iter()
+        .position(|s| Some(&s.output) == current_output.as_ref())
+        .unwrap_or(0);
+    rt.current_workspace = rt.screens[rt.current_screen].current_workspace;
+    rt.current_client = rt.screens[rt.current_screen]
+        .workspaces
+        .get(rt.current_workspace)
+        .and_then(|w| w.current_client);
+
+    emit_event(
+        app,
+        WmEvent::ScreensChanged {
+            screens: screens_amount,
+        },
+    );
+}
+
+/// Empty workspaces for screen with given index
+pub fn create_workspaces(config: &Configuration, index: usize) -> Vec<Workspace> {
+    (0..NUMBER_OF_DESKTOPS)
+        .map(|i| Workspace {
+            number: i as u64,
+            clients: Vec::new(),
+            current_client: None,
+            master_capacity: match config.desktops.master_capacities.get(index) {
+                Some(capacities) => capacities.get(i).copied().unwrap_or(1),
+                None => 1,
+            },
+            master_width: match config.desktops.splits.get(index) {
+                Some(splits) => splits.get(i).copied().unwrap_or(0.5),
+                None => 0.5,
+            },
+            arrange: match config.desktops.layouts.get(index) {
+                Some(layouts) => layouts.get(i).cloned().unwrap_or(ArrangeEngine::Tiled),
+                None => ArrangeEngine::Tiled,
+            },
+            gap: match config.desktops.gaps.get(index) {
+                Some(gaps) => gaps.get(i).copied().flatten(),
+                None => None,
+            },
+        })
+        .collect()
 }
 
 /// Create and set up workspaces
@@ -235,6 +500,7 @@ pub fn update_screens(app: &mut Application) {
 /// 2. If no workspaces create new
 /// 3. Get names and geometry for workspaces
 /// 4. Setup EWMH info of desktops
+/// 5. Update work areas
 ///
 pub fn update_desktops(app: &mut Application) {
     let mut desktop_names_ewmh: Vec<String> = vec![];
@@ -244,29 +510,14 @@ pub fn update_desktops(app: &mut Application) {
     for (index, screen) in app.runtime.screens.iter_mut().enumerate() {
         // 2. Create workspaces if needed
         if screen.workspaces.is_empty() {
-            for i in 0..NUMBER_OF_DESKTOPS {
-                let mw = if index < app.config.desktops.splits.len() {
-                    app.config.desktops.splits[index][i]
-                } else {
-                    0.5
-                };
-                screen.workspaces.push(Workspace {
-                    number: i as u64,
-                    clients: Vec::new(),
-                    current_client: None,
-                    master_capacity: 1,
-                    master_width: mw,
-                    arrange: ArrangeEngine::Tiled,
-                });
-            }
+            screen.workspaces = create_workspaces(&app.config, index);
         }
 
         // 3. Get names & geometry
         for i in 0..screen.workspaces.len() {
-            if index < app.config.desktops.names.len() {
-                desktop_names_ewmh.push(app.config.desktops.names[index][i].to_string());
-            } else {
-                desktop_names_ewmh.push(format!("{}", i + 1));
+            match app.config.desktops.names.get(index).and_then(|n| n.get(i)) {
+                Some(name) => desktop_names_ewmh.push(name.to_string()),
+                None => desktop_names_ewmh.push(format!("{}", i + 1)),
             }
             viewports.push(screen.x);
             viewports.push(screen.y);
@@ -274,6 +525,9 @@ pub fn update_desktops(app: &mut Application) {
     }
     // 4. SEt info
     update_desktop_ewmh_info(app, desktop_names_ewmh, viewports);
+
+    // 5. Work areas
+    update_workarea(app);
 }
 
 pub fn get_window_placement(app: &mut Application, win: u64, scan: bool) -> ((usize, usize), u64) {
@@ -365,7 +619,11 @@ pub fn get_window_placement(app: &mut Application, win: u64, scan: bool) -> ((us
             } else {
                 app.runtime.current_workspace
             };
-            return ((s, w), 0);
+            if s < app.runtime.screens.len() && w < app.runtime.screens[s].workspaces.len() {
+                return ((s, w), 0);
+            }
+            log!("==== Placement rule points at missing screen {s} or workspace {w}");
+            return (default_placement, 0);
         }
     }
 
@@ -8,32 +8,73 @@
 
 pub mod actions;
 pub mod arrange;
+pub mod check;
 pub mod config;
 pub mod events;
 pub mod helper;
+pub mod hooks;
+pub mod ipc;
+pub mod layout;
+pub mod loader;
 pub mod logic;
 pub mod manage;
 pub mod mouse;
+pub mod script;
 pub mod setup;
+pub mod socket;
+pub mod state;
 pub mod structs;
+pub mod tabs;
 pub mod utils;
 pub mod wrapper;
 
 use std::path::Path;
+use std::process::exit;
 
+use actions::reload_config;
+use check::check_config;
+use check::config_notes;
 use events::*;
 use helper::spawn;
+use ipc::*;
 use libc::LC_CTYPE;
+use loader::*;
 use setup::setup;
 use structs::Application;
+use tabs::tab_bar_expose;
 use wrapper::sys::no_zombies;
+use wrapper::sys::reload_fd;
+use wrapper::sys::reload_on_sighup;
 use wrapper::sys::set_locale;
+use wrapper::sys::take_reload_request;
+use wrapper::sys::wait_fds;
+use wrapper::xlib::connection_number;
 use wrapper::xlib::next_event;
+use wrapper::xlib::pending;
 use wrapper::xlib::EEvent;
 
 fn run(app: &mut Application) {
     log!("|===== run =====");
     while app.core.running {
+        if take_reload_request() {
+            reload_config(app);
+        }
+        handle_ipc(app);
+        // Wait on all connections instead of blocking in `XNextEvent`
+        // so signals and socket commands are handled
+        if pending(app.core.display) == 0 {
+            // One hotplug sends several events, screens are updated once for all of them
+            if app.runtime.screens_changed {
+                app.runtime.screens_changed = false;
+                screen_change_notify(app);
+                continue;
+            }
+            let mut fds = ipc_fds(app);
+            fds.push(connection_number(app.core.display));
+            fds.extend(reload_fd());
+            wait_fds(&fds, &ipc_write_fds(app));
+            continue;
+        }
         let event = next_event(app.core.display);
         match event {
             EEvent::KeyPress { key } => key_press(app, key),
@@ -54,6 +95,10 @@ fn run(app: &mut Application) {
             EEvent::ConfigureRequest {
                 configure_request_event,
             } => configure_request(app, configure_request_event),
+            EEvent::Expose { expose } => tab_bar_expose(app, expose),
+            EEvent::ScreenChangeNotify | EEvent::OutputChangeNotify => {
+                app.runtime.screens_changed = true;
+            }
             EEvent::Unmanaged { type_: _, name } => {
                 log!("|- Event `{}` is not currently managed", name);
             }
@@ -61,9 +106,72 @@ fn run(app: &mut Application) {
     }
 }
 
+const USAGE: &str = "Usage: rtwm [--check-config] [--help] [--version]
+
+Options:
+    --check-config  Validate configuration without opening display
+    -h, --help      Print this message
+    -v, --version   Print version";
+
+/// Load configuration like on startup and print problems found in it.
+/// Returns exit code
+fn check_config_mode() -> i32 {
+    let config = match config_path() {
+        Some(path) if path.exists() => {
+            println!("Checking {}", path.display());
+            match read_config_file(&path) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return 1;
+                }
+            }
+        }
+        _ => {
+            println!("No config file found, checking built-in configuration");
+            config::config()
+        }
+    };
+
+    for note in config_notes(&config) {
+        println!("note: {note}");
+    }
+    let problems = check_config(&config);
+    for problem in &problems {
+        eprintln!("error: {problem}");
+    }
+    if problems.is_empty() {
+        println!("Configuration is OK");
+        0
+    } else {
+        eprintln!("Found {} problem(s)", problems.len());
+        1
+    }
+}
+
 fn main() {
+    if let Some(arg) = std::env::args().nth(1) {
+        let code = match arg.as_str() {
+            "--check-config" => check_config_mode(),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                0
+            }
+            "-v" | "--version" => {
+                println!("rtwm {}", env!("CARGO_PKG_VERSION"));
+                0
+            }
+            _ => {
+                eprintln!("Unknown argument `{arg}`\n{USAGE}");
+                1
+            }
+        };
+        exit(code);
+    }
+
     set_locale(LC_CTYPE, "");
     no_zombies();
+    reload_on_sighup();
     let mut app: Application = setup();
     if !Path::new("/tmp/rtwmrunning").exists() {
         for rule in app.config.autostart.clone() {
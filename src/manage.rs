@@ -14,6 +14,7 @@ use x11::xlib::XA_WINDOW;
 use crate::arrange::*;
 use crate::config;
 use crate::helper::*;
+use crate::ipc::emit_event;
 use crate::logic::*;
 use crate::structs::*;
 use crate::utils::*;
@@ -33,11 +34,15 @@ use crate::wrapper::xlib::*;
 /// 5. Get properties
 /// 6. Update hints by running [`update_normal_hints`]
 /// 7. Set flags
+///     * Float dialogs, center utility, toolbar & splash windows
+///     * Keep notifications & menus above without focusing them
+///     * Keep desktop windows below on every workspace
 /// 8. Set input mask for events
 /// 9. set previously active client border to normal
 /// 10. Get desktop info left from previous wm session
 /// 11. Find where to place window
 /// 12. Add to stack
+///     * Apply rest of `_NET_WM_STATE` atoms
 /// 13. Update client list & desktops
 /// 14. Configure window
 /// 15. Arrange clients
@@ -67,7 +72,7 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
         select_input(
             app.core.display,
             win,
-            StructureNotifyMask | SubstructureNotifyMask,
+            PropertyChangeMask | StructureNotifyMask | SubstructureNotifyMask,
         );
         return;
     }
@@ -79,6 +84,7 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     c.h = wa.height as u32;
     c.ow = c.w;
     c.oh = c.h;
+    c.weight = 1.0;
     c.x = wa.x
         + app.runtime.screens[app.runtime.current_screen]
             .bar_offsets
@@ -92,7 +98,9 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     println!("{:#?}", c);
 
     // 5. Properties
-    let state = get_atom_prop(app, win, app.atoms.net_wm_state);
+    c.window_name = get_text_property(app.core.display, win, app.atoms.net_wm_name)
+        .unwrap_or_else(|| "_".to_string());
+    let states = get_atom_list_prop(app, win, app.atoms.net_wm_state);
     let wtype = get_atom_prop(app, win, app.atoms.net_wm_window_type);
 
     let (instance, class) = {
@@ -110,20 +118,51 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     update_normal_hints(app, &mut c);
 
     // 7. Set flags
-    if state == app.atoms.net_wm_fullscreen {
+    if states.contains(&app.atoms.net_wm_fullscreen) {
         c.floating = true;
         c.fullscreen = true;
     }
-    if wtype == app.atoms.net_wm_window_type_dialog {
+    let atoms = &app.atoms;
+    let centered = [
+        atoms.net_wm_window_type_utility,
+        atoms.net_wm_window_type_toolbar,
+        atoms.net_wm_window_type_splash,
+    ]
+    .contains(&wtype);
+    let popup = [
+        atoms.net_wm_window_type_notification,
+        atoms.net_wm_window_type_menu,
+        atoms.net_wm_window_type_dropdown_menu,
+        atoms.net_wm_window_type_popup_menu,
+        atoms.net_wm_window_type_tooltip,
+        atoms.net_wm_window_type_combo,
+        atoms.net_wm_window_type_dnd,
+    ]
+    .contains(&wtype);
+    if wtype == atoms.net_wm_window_type_dialog || centered {
         c.floating = true;
     }
+    if popup {
+        // Stays where it asked to be, above others & without stealing focus
+        c.floating = true;
+        c.above = true;
+        c.never_focus = true;
+    }
+    if wtype == atoms.net_wm_window_type_desktop {
+        // Covers screen below everything on every workspace
+        c.floating = true;
+        c.below = true;
+        c.sticky = true;
+        c.never_focus = true;
+        c.desktop = true;
+    }
 
     if !c.floating {
         c.floating = c.fixed || trans != 0;
     }
 
     c.border = if c.floating {
-        app.config.border_size as u32
+        floating_border(&app.config, &c)
     } else {
         0
     };
@@ -136,8 +175,10 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     );
 
     // 9. Unfocus current windows
-    if let Some(cw) = get_current_client_id(app) {
-        unfocus(app, cw);
+    if !c.never_focus {
+        if let Some(cw) = get_current_client_id(app) {
+            unfocus(app, cw);
+        }
     }
 
     let screen = &app.runtime.screens[client_screen];
@@ -158,24 +199,58 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
         c.y += screen.y as i32;
     }
 
+    if centered {
+        c.x = screen.x as i32 + (screen.width as i32 - c.w as i32) / 2;
+        c.y = screen.y as i32 + (screen.height as i32 - c.h as i32) / 2;
+    }
+    if c.desktop {
+        c.x = screen.x as i32;
+        c.y = screen.y as i32;
+        c.w = screen.width as u32;
+        c.h = screen.height as u32;
+    }
+
     let workspace = &mut app.runtime.screens[client_screen].workspaces[client_workspace];
 
     // 12. Add window to stack
-    workspace.current_client = Some(workspace.clients.len());
-    app.runtime.current_client = workspace.current_client;
+    if !c.never_focus {
+        workspace.current_client = Some(workspace.clients.len());
+        app.runtime.current_client = workspace.current_client;
+    }
+    let name = c.window_name.clone();
+    let desktop = c.desktop;
     workspace.clients.push(c);
+    emit_event(
+        app,
+        WmEvent::WindowOpened {
+            window_id: win,
+            name,
+            screen: client_screen,
+            workspace: client_workspace,
+        },
+    );
+
+    // Rest of states is applied to managed client & written back
+    for state in states {
+        if state != app.atoms.net_wm_fullscreen {
+            set_client_state(app, win, state, NET_WM_STATE_ADD);
+        }
+    }
+    update_net_wm_state(app, win);
 
     // 13. Update client list & window desktop
-    change_property(
-        app.core.display,
-        app.core.root_win,
-        app.atoms.net_client_list,
-        XA_WINDOW,
-        32,
-        PropModeAppend,
-        &win as *const u64 as *mut u8,
-        1,
-    );
+    if !desktop {
+        change_property(
+            app.core.display,
+            app.core.root_win,
+            app.atoms.net_client_list,
+            XA_WINDOW,
+            32,
+            PropModeAppend,
+            &win as *const u64 as *mut u8,
+            1,
+        );
+    }
     let cur_workspace: usize = client_workspace + client_screen * config::NUMBER_OF_DESKTOPS;
     update_client_desktop(app, win, cur_workspace as u64);
 
@@ -217,53 +292,51 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
 
     if client_screen == app.runtime.current_screen
         && client_workspace == app.runtime.current_workspace
+        && accepts_focus(app, win)
     {
         focus(app, win);
     }
 }
 
+/// Recalculate bar offsets of all screens from docks
+///
+/// 1. Reset offsets
+/// 2. Add space reserved by every dock
+///     * Docks with strut reserve it on every screen they overlap
+///     * Others are guessed from geometry on screen they are placed on
+/// 3. Publish work areas
+/// 4. Rearrange & show current workspace
 pub fn update_docks(app: &mut Application) {
+    // 1. Reset
     for screen in &mut app.runtime.screens {
         screen.bar_offsets = BarOffsets::default();
     }
 
+    // 2. Add up reserved space
+    // Struts are relative to root window edges, which may lie outside of every screen
+    let (root_w, root_h) = display_size(app.core.display);
+    let (root_w, root_h) = (root_w as i64, root_h as i64);
     for bar in &app.runtime.bars {
-        let dx = bar.x;
-        let dy = bar.y;
-        let dw = bar.w;
-        let dh = bar.h;
-        for screen in &mut app.runtime.screens {
-            if dx >= screen.x
-                && dx < (screen.x + screen.width)
-                && dy >= screen.y
-                && dy < (screen.y + screen.height)
-            {
-                let mut ba = screen.bar_offsets;
-                // Found corresponding screen
-                if dw > dh {
-                    // dock is horizontal
-                    if dy == screen.y {
-                        // dock is on the top
-                        ba.up = dh;
-                    } else {
-                        // dock is on the bottom
-                        ba.down = dh;
-                    }
-                } else {
-                    // dock is vertical
-                    if dx == screen.x {
-                        // dock is on the left
-                        ba.left = dw;
-                    } else {
-                        // dock is on the right
-                        ba.right = dw;
-                    }
+        match bar.strut {
+            Some(strut) => {
+                for screen in &mut app.runtime.screens {
+                    add_strut_offsets(screen, &strut, root_w, root_h);
+                }
+            }
+            None => {
+                if let Some(screen) = app.runtime.screens.iter_mut().find(|s| {
+                    bar.x >= s.x && bar.x < s.x + s.width && bar.y >= s.y && bar.y < s.y + s.height
+                }) {
+                    add_guessed_offsets(screen, bar);
                 }
-                screen.bar_offsets = ba;
-                break;
             }
         }
     }
+
+    // 3. Work areas
+    update_workarea(app);
+
+    // 4. Rearrange
     arrange_all(app);
     show_workspace(
         app,
@@ -272,21 +345,104 @@ pub fn update_docks(app: &mut Application) {
     );
 }
 
+/// Add part of strut falling on screen to its offsets
+fn add_strut_offsets(screen: &mut Screen, strut: &Strut, root_w: i64, root_h: i64) {
+    let (sx, sy, sw, sh) = (screen.x, screen.y, screen.width, screen.height);
+    // Inclusive range [start, end] intersects screen span [from, from + len)
+    let overlaps = |start: i64, end: i64, from: i64, len: i64| start < from + len && end >= from;
+    let reserved = |size: i64, len: i64| size.clamp(0, len) as usize;
+
+    let ba = &mut screen.bar_offsets;
+    if strut.top > 0 && overlaps(strut.top_start_x, strut.top_end_x, sx, sw) {
+        ba.up += reserved(strut.top - sy, sh);
+    }
+    if strut.bottom > 0 && overlaps(strut.bottom_start_x, strut.bottom_end_x, sx, sw) {
+        ba.down += reserved(sy + sh - (root_h - strut.bottom), sh);
+    }
+    if strut.left > 0 && overlaps(strut.left_start_y, strut.left_end_y, sy, sh) {
+        ba.left += reserved(strut.left - sx, sw);
+    }
+    if strut.right > 0 && overlaps(strut.right_start_y, strut.right_end_y, sy, sh) {
+        ba.right += reserved(sx + sw - (root_w - strut.right), sw);
+    }
+}
+
+/// Guess edge of dock without strut from its shape & position
+fn add_guessed_offsets(screen: &mut Screen, bar: &Bar) {
+    let ba = &mut screen.bar_offsets;
+    if bar.w > bar.h {
+        // dock is horizontal
+        if bar.y == screen.y {
+            ba.up += bar.h;
+        } else {
+            ba.down += bar.h;
+        }
+    } else {
+        // dock is vertical
+        if bar.x == screen.x {
+            ba.left += bar.w;
+        } else {
+            ba.right += bar.w;
+        }
+    }
+}
+
+/// Read `_NET_WM_STRUT_PARTIAL` or, if it is missing, `_NET_WM_STRUT` of dock
+pub fn get_strut(app: &mut Application, win: u64) -> Option<Strut> {
+    let values = get_cardinal_prop(app, win, app.atoms.net_wm_strut_partial, 12)
+        .filter(|v| v.len() == 12)
+        .or_else(|| {
+            // Plain strut reserves whole edges
+            get_cardinal_prop(app, win, app.atoms.net_wm_strut, 4)
+                .filter(|v| v.len() == 4)
+                .map(|v| [v, [0, i64::MAX].repeat(4)].concat())
+        })?;
+    Some(Strut {
+        left: values[0],
+        right: values[1],
+        top: values[2],
+        bottom: values[3],
+        left_start_y: values[4],
+        left_end_y: values[5],
+        right_start_y: values[6],
+        right_end_y: values[7],
+        top_start_x: values[8],
+        top_end_x: values[9],
+        bottom_start_x: values[10],
+        bottom_end_x: values[11],
+    })
+}
+
 pub fn attach_dock(app: &mut Application, wa: &XWindowAttributes, win: u64) {
     let dx = wa.x as i64;
     let dy = wa.y as i64;
     let dw = wa.width as usize;
     let dh = wa.height as usize;
+    let strut = get_strut(app, win);
     app.runtime.bars.push(Bar {
         window_id: win,
         x: dx,
         y: dy,
         w: dw,
         h: dh,
+        strut,
     });
     update_docks(app);
 }
 
+/// Re-read strut of dock after it changed. Returns `false` if window is not a dock
+pub fn update_dock_strut(app: &mut Application, win: u64) -> bool {
+    if !app.runtime.bars.iter().any(|b| b.window_id == win) {
+        return false;
+    }
+    let strut = get_strut(app, win);
+    if let Some(bar) = app.runtime.bars.iter_mut().find(|b| b.window_id == win) {
+        bar.strut = strut;
+    }
+    update_docks(app);
+    true
+}
+
 pub fn detach_dock(app: &mut Application, win: u64) {
     app.runtime.bars.retain(|b| b.window_id != win);
     update_docks(app);
@@ -300,6 +456,14 @@ pub fn unmanage_window(app: &mut Application, win: u64) {
         log!("   |- Found window {} at indexes {}, {}, {}", win, s, w, c);
         // delete_property(app.core.display, win, app.atoms.net_wm_desktop);
         app.runtime.screens[s].workspaces[w].clients.remove(c);
+        emit_event(
+            app,
+            WmEvent::WindowClosed {
+                window_id: win,
+                screen: s,
+                workspace: w,
+            },
+        );
         shift_current_client(app, s, w);
 
         grab_server(app.core.display);
@@ -0,0 +1,257 @@
+//! Rhai scripts run by `Script` action
+//!
+//! Scripts are read from `$XDG_CONFIG_HOME/rtwm/scripts/NAME.rhai` (or `~/.config/rtwm/scripts/`)
+//! every time action runs, so they can be edited without reloading WM.
+//!
+//! State is exposed as maps with same layout as `query` socket command (see [`crate::state`]):
+//! `state()`, `screens()`, `clients()`, `focus()`. Windows are referred to by `window_id`,
+//! `window_class(id)` and `window_instance(id)` return `WM_CLASS` of window.
+//!
+//! Actions work on focused window like key bindings do:
+//! `focus_on_workspace(n)`, `move_to_workspace(n)`, `focus_on_screen("next"|"prev")`,
+//! `move_to_screen("next"|"prev")`, `set_layout("mono")`, `toggle_float()`, `kill_client()`,
+//! `spawn("cmd")`, `spawn(["cmd", "arg"])`, `focus_window(id)`
+//! and `action("UpdateMasterWidth 0.05")` for anything else.
+//! Layouts are named like in `SetArrangeEngine`, in kebab-case: `tiled`, `centered-master`, ...
+//! or as registered in `config::layouts()`.
+//!
+//! ```text
+//! // Move all browsers to workspace 5 and show them one at a time
+//! for c in clients() {
+//!     if window_class(c.window_id) == "firefox" {
+//!         focus_window(c.window_id);
+//!         move_to_workspace(4);
+//!     }
+//! }
+//! focus_on_workspace(4);
+//! set_layout("mono");
+//! ```
+
+use std::ffi::CString;
+use std::path::PathBuf;
+
+use rhai::Array;
+use rhai::Dynamic;
+use rhai::Engine;
+use rhai::EvalAltResult;
+
+use crate::actions::*;
+use crate::config::NUMBER_OF_DESKTOPS;
+use crate::helper::*;
+use crate::ipc::parse_command;
+use crate::loader::config_path;
+use crate::logic::*;
+use crate::state::*;
+use crate::structs::*;
+use crate::utils::*;
+use crate::wrapper::xlib::*;
+
+/// Limit on script length in operations, stops endless loops from freezing WM
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Directory with user scripts
+pub fn scripts_dir() -> Option<PathBuf> {
+    Some(config_path()?.parent()?.join("scripts"))
+}
+
+/// Path of script with given name
+pub fn script_path(name: &str) -> Option<PathBuf> {
+    Some(scripts_dir()?.join(format!("{name}.rhai")))
+}
+
+/// Run script with given name
+///
+/// 1. Read script
+/// 2. Create engine with WM functions bound to `app`
+/// 3. Run
+pub fn run_script(app: &mut Application, name: &str) -> Result<(), String> {
+    log!("   |- Got `Script` Action: {}", name);
+    // 1. Read
+    if name.is_empty() || name.contains('/') {
+        return Err(format!("invalid script name `{name}`"));
+    }
+    let path = match script_path(name) {
+        Some(path) => path,
+        None => return Err("can't find config directory".to_string()),
+    };
+    let source = std::fs::read_to_string(&path)
+        .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+
+    // 2. Engine
+    // SAFETY: engine is dropped before this function returns, while `app` is still
+    // borrowed by it and not used otherwise
+    let engine = unsafe { create_engine(app) };
+
+    // 3. Run
+    engine
+        .run(&source)
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Application script is running for, captured by WM functions of engine
+///
+/// Engine functions have to be `'static`, so borrow is kept as pointer. Only one function
+/// runs at a time and none of them calls another, so at most one `&mut Application`
+/// made from it exists at once.
+#[derive(Clone, Copy)]
+struct AppHandle(*mut Application);
+
+impl AppHandle {
+    /// # Safety
+    ///
+    /// `app` must outlive every use of handle and must not be used otherwise meanwhile
+    unsafe fn new(app: &mut Application) -> AppHandle {
+        AppHandle(app)
+    }
+
+    /// Run closure on application
+    fn with<T>(self, f: impl FnOnce(&mut Application) -> T) -> T {
+        // SAFETY: pointer is valid & unaliased, guaranteed by caller of `AppHandle::new`
+        unsafe { f(&mut *self.0) }
+    }
+}
+
+fn to_dynamic(value: serde_json::Value) -> ScriptResult<Dynamic> {
+    rhai::serde::to_dynamic(value)
+}
+
+fn check_workspace(n: i64) -> ScriptResult<u64> {
+    if n < 0 || n as usize >= NUMBER_OF_DESKTOPS {
+        return Err(format!(
+            "workspace {n} does not exist, there are {NUMBER_OF_DESKTOPS} desktops per screen"
+        )
+        .into());
+    }
+    Ok(n as u64)
+}
+
+fn parse_direction(d: &str) -> ScriptResult<ScreenSwitching> {
+    match d.to_lowercase().as_str() {
+        "next" => Ok(ScreenSwitching::Next),
+        "prev" | "previous" => Ok(ScreenSwitching::Previous),
+        _ => Err(format!("expected `next` or `prev`, got `{d}`").into()),
+    }
+}
+
+fn parse_layout(app: &Application, layout: &str) -> ScriptResult<ArrangeEngine> {
+    let ae = ArrangeEngine::from_name(layout);
+    match app.runtime.layouts.get(ae.name()) {
+        Some(_) => Ok(ae),
+        None => Err(format!("unknown layout `{layout}`").into()),
+    }
+}
+
+fn class_hint(app: &mut Application, win: i64) -> ClassHint {
+    let mut ch = ClassHint::default();
+    get_class_hint(app.core.display, win as u64, &mut ch);
+    ch
+}
+
+/// Focus window wherever it is, switching screen and workspace if needed
+fn focus_window(app: &mut Application, win: u64) -> ScriptResult<()> {
+    let (s, w, _) = match find_window_indexes(app, win) {
+        Some(indexes) => indexes,
+        None => return Err(format!("window {win} is not managed").into()),
+    };
+    focus_on_workspace(app, (s * NUMBER_OF_DESKTOPS + w) as u64, false);
+    if let Some(cw) = get_current_client_id(app) {
+        unfocus(app, cw);
+    }
+    focus(app, win);
+    show_workspace(app, s, w);
+    Ok(())
+}
+
+fn spawn_command(app: &mut Application, args: Vec<String>) -> ScriptResult<()> {
+    let args: Vec<CString> = args
+        .into_iter()
+        .map(CString::new)
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    if args.is_empty() || args[0].is_empty() {
+        return Err("empty command".into());
+    }
+    spawn(app, &args, None);
+    Ok(())
+}
+
+/// Create engine with WM functions bound to `app`
+///
+/// # Safety
+///
+/// Engine must be dropped before `app` is used again, see [`AppHandle::new`]
+unsafe fn create_engine(app: &mut Application) -> Engine {
+    // SAFETY: forwarded to caller
+    let app = unsafe { AppHandle::new(app) };
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    // State
+    engine.register_fn("state", move || to_dynamic(app.with(|a| state_json(a))));
+    engine.register_fn("screens", move || to_dynamic(app.with(|a| screens_json(a))));
+    engine.register_fn("clients", move || to_dynamic(app.with(|a| clients_json(a))));
+    engine.register_fn("focus", move || to_dynamic(app.with(|a| focus_json(a))));
+    engine.register_fn("window_class", move |win: i64| {
+        app.with(|a| class_hint(a, win).res_class.unwrap_or_default())
+    });
+    engine.register_fn("window_instance", move |win: i64| {
+        app.with(|a| class_hint(a, win).res_name.unwrap_or_default())
+    });
+
+    // Actions
+    engine.register_fn("focus_on_workspace", move |n: i64| -> ScriptResult<()> {
+        let n = check_workspace(n)?;
+        app.with(|a| focus_on_workspace(a, n, true));
+        Ok(())
+    });
+    engine.register_fn("move_to_workspace", move |n: i64| -> ScriptResult<()> {
+        let n = check_workspace(n)?;
+        app.with(|a| move_to_workspace(a, n));
+        Ok(())
+    });
+    engine.register_fn("focus_on_screen", move |d: &str| -> ScriptResult<()> {
+        let d = parse_direction(d)?;
+        app.with(|a| focus_on_screen(a, d));
+        Ok(())
+    });
+    engine.register_fn("move_to_screen", move |d: &str| -> ScriptResult<()> {
+        let d = parse_direction(d)?;
+        app.with(|a| move_to_screen(a, d));
+        Ok(())
+    });
+    engine.register_fn("set_layout", move |layout: &str| -> ScriptResult<()> {
+        app.with(|a| {
+            let layout = parse_layout(a, layout)?;
+            run_action(a, &ActionResult::SetArrangeEngine(layout));
+            Ok(())
+        })
+    });
+    engine.register_fn("toggle_float", move || app.with(toggle_float));
+    engine.register_fn("kill_client", move || app.with(kill_client));
+    engine.register_fn("focus_window", move |win: i64| {
+        app.with(|a| focus_window(a, win as u64))
+    });
+    engine.register_fn("spawn", move |cmd: &str| {
+        let args = cmd.split_whitespace().map(String::from).collect();
+        app.with(|a| spawn_command(a, args))
+    });
+    engine.register_fn("spawn", move |cmd: Array| {
+        let args = cmd.into_iter().map(|arg| arg.to_string()).collect();
+        app.with(|a| spawn_command(a, args))
+    });
+    engine.register_fn("action", move |line: &str| -> ScriptResult<()> {
+        let action = parse_command(line)?;
+        if let ActionResult::Script(_) = action {
+            return Err("scripts can't run other scripts".into());
+        }
+        if let ActionResult::FocusOnWorkspace(n) | ActionResult::MoveToWorkspace(n) = action {
+            check_workspace(n as i64)?;
+        }
+        app.with(|a| run_action(a, &action));
+        Ok(())
+    });
+
+    engine
+}
@@ -1,11 +1,14 @@
 //! Code for setting up WM. Intented to be ran once
 
 use crate::actions::focus_on_workspace;
-use crate::config::*;
+use crate::ipc::init_ipc;
+use crate::layout::load_layouts;
+use crate::loader::load_config;
 use crate::manage::*;
 use crate::structs::*;
 use crate::utils::*;
 use crate::wrapper::xlib::*;
+use crate::wrapper::xrandr::xrandr_init;
 
 use std::process::exit;
 use std::vec;
@@ -42,15 +45,17 @@ use x11::xlib::Display;
 /// 4. Create helper window
 ///     * Call [`init_wm_check`]
 /// 5. Create screens
-///     * Call [`update_screens`]
+///     * Call [`xrandr_init`] & [`update_screens`]
 /// 6. Create workspaces
 ///     * Call [`update_desktops`]
 /// 7. Setup shortcuts
 ///     * Call [`init_actions`]
 /// 8. Set error handler for x11
 ///     * Call [`set_error_handler`]
-/// 9. Set input masks
-/// 10. Focus on workspace 1
+/// 9. Create control socket
+///     * Call [`init_ipc`]
+/// 10. Set input masks
+/// 11. Focus on workspace 1
 pub fn setup() -> Application {
     // 1. Open display
     let display = match open_display(None) {
@@ -64,12 +69,13 @@ pub fn setup() -> Application {
 
     // 2. Create struct
     let mut app = Application {
-        config: config(),
+        config: load_config(),
         core: WmCore {
             display,
             root_win,
             wm_check_win: 0,
             running: true,
+            ipc: None,
         },
         runtime: Runtime {
             mouse_state: MouseState {
@@ -83,6 +89,11 @@ pub fn setup() -> Application {
             current_client: None,
             bars: vec![],
             autostart_rules: vec![],
+            detached_screens: vec![],
+            layouts: load_layouts(),
+            tab_bars: vec![],
+            tab_font: None,
+            screens_changed: false,
         },
         atoms: Atoms {
             utf8string: 0,
@@ -97,29 +108,56 @@ pub fn setup() -> Application {
             net_wm_name: 0,
             net_wm_state: 0,
             net_wm_state_demands_attention: 0,
+            net_wm_state_maximized_vert: 0,
+            net_wm_state_maximized_horz: 0,
+            net_wm_state_above: 0,
+            net_wm_state_below: 0,
+            net_wm_state_sticky: 0,
+            net_wm_state_hidden: 0,
+            net_wm_state_skip_taskbar: 0,
+            net_wm_state_skip_pager: 0,
             net_wm_fullscreen: 0,
             net_wm_window_type: 0,
             net_wm_window_type_dock: 0,
             net_wm_window_type_dialog: 0,
+            net_wm_window_type_desktop: 0,
+            net_wm_window_type_toolbar: 0,
+            net_wm_window_type_menu: 0,
+            net_wm_window_type_utility: 0,
+            net_wm_window_type_splash: 0,
+            net_wm_window_type_notification: 0,
+            net_wm_window_type_dropdown_menu: 0,
+            net_wm_window_type_popup_menu: 0,
+            net_wm_window_type_tooltip: 0,
+            net_wm_window_type_combo: 0,
+            net_wm_window_type_dnd: 0,
+            net_wm_strut: 0,
+            net_wm_strut_partial: 0,
             net_client_list: 0,
             net_number_of_desktops: 0,
             net_current_desktop: 0,
             net_desktop_viewport: 0,
+            net_desktop_geometry: 0,
+            net_workarea: 0,
             net_desktop_names: 0,
             net_wm_desktop: 0,
             net_wm_pid: 0,
         },
     };
 
-    // 3-8
+    // 3-9
     init_supported_atoms(&mut app);
     init_wm_check(&mut app);
+    if !xrandr_init(app.core.display, app.core.root_win) {
+        log!("|- XRandR 1.5 is not available, monitor hotplug is not tracked");
+    }
     update_screens(&mut app);
     update_desktops(&mut app);
     init_actions(&mut app);
     set_error_handler();
+    init_ipc(&mut app);
 
-    // 9. Input mask
+    // 10. Input mask
     let mut wa: XSetWindowAttributes = XSetWindowAttributes {
         background_pixmap: 0,
         background_pixel: 0,
@@ -156,7 +194,7 @@ pub fn setup() -> Application {
 
     select_input(app.core.display, app.core.root_win, wa.event_mask);
 
-    // 10. Focus
+    // 11. Focus
     focus_on_workspace(&mut app, 0, false);
 
     app
@@ -243,16 +281,39 @@ pub fn init_supported_atoms(app: &mut Application) {
         net_wm_name: intern_atom!("_NET_WM_NAME"),
         net_wm_state: intern_atom!("_NET_WM_STATE"),
         net_wm_state_demands_attention: intern_atom!("_NET_WM_STATE_DEMANDS_ATTENTION"),
+        net_wm_state_maximized_vert: intern_atom!("_NET_WM_STATE_MAXIMIZED_VERT"),
+        net_wm_state_maximized_horz: intern_atom!("_NET_WM_STATE_MAXIMIZED_HORZ"),
+        net_wm_state_above: intern_atom!("_NET_WM_STATE_ABOVE"),
+        net_wm_state_below: intern_atom!("_NET_WM_STATE_BELOW"),
+        net_wm_state_sticky: intern_atom!("_NET_WM_STATE_STICKY"),
+        net_wm_state_hidden: intern_atom!("_NET_WM_STATE_HIDDEN"),
+        net_wm_state_skip_taskbar: intern_atom!("_NET_WM_STATE_SKIP_TASKBAR"),
+        net_wm_state_skip_pager: intern_atom!("_NET_WM_STATE_SKIP_PAGER"),
         net_wm_check: intern_atom!("_NET_SUPPORTING_WM_CHECK"),
         net_wm_fullscreen: intern_atom!("_NET_WM_STATE_FULLSCREEN"),
         net_wm_window_type: intern_atom!("_NET_WM_WINDOW_TYPE"),
         net_wm_window_type_dialog: intern_atom!("_NET_WM_WINDOW_TYPE_DIALOG"),
         net_wm_window_type_dock: intern_atom!("_NET_WM_WINDOW_TYPE_DOCK"),
+        net_wm_window_type_desktop: intern_atom!("_NET_WM_WINDOW_TYPE_DESKTOP"),
+        net_wm_window_type_toolbar: intern_atom!("_NET_WM_WINDOW_TYPE_TOOLBAR"),
+        net_wm_window_type_menu: intern_atom!("_NET_WM_WINDOW_TYPE_MENU"),
+        net_wm_window_type_utility: intern_atom!("_NET_WM_WINDOW_TYPE_UTILITY"),
+        net_wm_window_type_splash: intern_atom!("_NET_WM_WINDOW_TYPE_SPLASH"),
+        net_wm_window_type_notification: intern_atom!("_NET_WM_WINDOW_TYPE_NOTIFICATION"),
+        net_wm_window_type_dropdown_menu: intern_atom!("_NET_WM_WINDOW_TYPE_DROPDOWN_MENU"),
+        net_wm_window_type_popup_menu: intern_atom!("_NET_WM_WINDOW_TYPE_POPUP_MENU"),
+        net_wm_window_type_tooltip: intern_atom!("_NET_WM_WINDOW_TYPE_TOOLTIP"),
+        net_wm_window_type_combo: intern_atom!("_NET_WM_WINDOW_TYPE_COMBO"),
+        net_wm_window_type_dnd: intern_atom!("_NET_WM_WINDOW_TYPE_DND"),
+        net_wm_strut: intern_atom!("_NET_WM_STRUT"),
+        net_wm_strut_partial: intern_atom!("_NET_WM_STRUT_PARTIAL"),
         net_client_list: intern_atom!("_NET_CLIENT_LIST"),
         net_number_of_desktops: intern_atom!("_NET_NUMBER_OF_DESKTOPS"),
         net_current_desktop: intern_atom!("_NET_CURRENT_DESKTOP"),
         net_desktop_names: intern_atom!("_NET_DESKTOP_NAMES"),
         net_desktop_viewport: intern_atom!("_NET_DESKTOP_VIEWPORT"),
+        net_desktop_geometry: intern_atom!("_NET_DESKTOP_GEOMETRY"),
+        net_workarea: intern_atom!("_NET_WORKAREA"),
         net_wm_desktop: intern_atom!("_NET_WM_DESKTOP"),
         net_wm_pid: intern_atom!("_NET_WM_PID"),
     };
@@ -264,13 +325,37 @@ pub fn init_supported_atoms(app: &mut Application) {
         app.atoms.net_wm_fullscreen,
         app.atoms.net_wm_window_type,
         app.atoms.net_wm_window_type_dialog,
+        app.atoms.net_wm_window_type_dock,
+        app.atoms.net_wm_window_type_desktop,
+        app.atoms.net_wm_window_type_toolbar,
+        app.atoms.net_wm_window_type_menu,
+        app.atoms.net_wm_window_type_utility,
+        app.atoms.net_wm_window_type_splash,
+        app.atoms.net_wm_window_type_notification,
+        app.atoms.net_wm_window_type_dropdown_menu,
+        app.atoms.net_wm_window_type_popup_menu,
+        app.atoms.net_wm_window_type_tooltip,
+        app.atoms.net_wm_window_type_combo,
+        app.atoms.net_wm_window_type_dnd,
         app.atoms.net_client_list,
         app.atoms.net_wm_state,
         app.atoms.net_number_of_desktops,
         app.atoms.net_current_desktop,
         app.atoms.net_desktop_viewport,
+        app.atoms.net_desktop_geometry,
+        app.atoms.net_workarea,
         app.atoms.net_desktop_names,
         app.atoms.net_wm_state_demands_attention,
+        app.atoms.net_wm_state_maximized_vert,
+        app.atoms.net_wm_state_maximized_horz,
+        app.atoms.net_wm_state_above,
+        app.atoms.net_wm_state_below,
+        app.atoms.net_wm_state_sticky,
+        app.atoms.net_wm_state_hidden,
+        app.atoms.net_wm_state_skip_taskbar,
+        app.atoms.net_wm_state_skip_pager,
+        app.atoms.net_wm_strut,
+        app.atoms.net_wm_strut_partial,
     ];
 
     change_property(
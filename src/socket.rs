@@ -0,0 +1,32 @@
+//! Control socket location & command naming, shared by `rtwm` and `rtwmc`
+//!
+//! Included into `rtwmc` by path, so it must not use other modules of the crate.
+
+use std::path::PathBuf;
+
+/// Path of control socket for current display. `$RTWM_SOCKET` wins if set,
+/// it is exported by WM to every program it spawns
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("RTWM_SOCKET") {
+        return PathBuf::from(path);
+    }
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => std::env::temp_dir(),
+    };
+    let display = std::env::var("DISPLAY").unwrap_or_default();
+    dir.join(format!("rtwm-{display}.sock"))
+}
+
+/// `move-to-screen` -> `MoveToScreen`, `next` -> `Next`
+pub fn camel_case(word: &str) -> String {
+    word.split(['-', '_'])
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
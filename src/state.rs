@@ -0,0 +1,228 @@
+//! JSON snapshot of WM state, used by `query` socket command and `DumpInfo` action
+//!
+//! Schema (version 1). Fields are only ever added, never renamed or removed
+//! without bumping `version`:
+//! ```text
+//! {
+//!   "version": 1,
+//!   "focus": {
+//!     "screen": 0, "workspace": 0,          // indexes
+//!     "client": 0 | null,                   // index in workspace clients
+//!     "window_id": 4194307 | null
+//!   },
+//!   "screens": [{
+//!     "index": 0, "number": 0, "output": "HDMI-1",
+//!     "x": 0, "y": 0, "width": 1920, "height": 1080,
+//!     "bar_offsets": { "left": 0, "up": 24, "right": 0, "down": 0 },
+//!     "current_workspace": 0,
+//!     "workspaces": [{
+//!       "index": 0,
+//!       "desktop": 0,                       // _NET_WM_DESKTOP of its clients
+//!       "name": "1",
+//!       "layout": "tiled",                  // name accepted by `rtwmc layout`
+//!       "master_capacity": 1,
+//!       "master_width": 0.5,
+//!       "current_client": 0 | null,
+//!       "clients": [{
+//!         "window_id": 4194307,
+//!         "name": "alacritty",
+//!         "x": 4, "y": 28, "width": 952, "height": 1044, "border": 2,
+//!         "weight": 1.0,
+//!         "floating": false, "fullscreen": false, "urgent": false,
+//!         "maximized_vert": false, "maximized_horz": false, "above": false, "below": false,
+//!         "sticky": false, "hidden": false, "skip_taskbar": false, "skip_pager": false,
+//!         "never_focus": false,             // notifications, menus & desktop windows
+//!         "desktop": false,                 // desktop window, e.g. file manager icons
+//!         "visible": true, "focused": true
+//!       }]
+//!     }]
+//!   }]
+//! }
+//! ```
+//! `query clients` returns flat list of clients, each with `screen` and `workspace` indexes added.
+
+use std::path::PathBuf;
+
+use serde_json::json;
+use serde_json::Value;
+
+use crate::config::NUMBER_OF_DESKTOPS;
+use crate::socket::socket_path;
+use crate::structs::*;
+use crate::utils::*;
+
+pub const STATE_VERSION: u64 = 1;
+
+fn client_json(client: &Client, focused: bool) -> Value {
+    json!({
+        "window_id": client.window_id,
+        "name": client.window_name,
+        "x": client.x,
+        "y": client.y,
+        "width": client.w,
+        "height": client.h,
+        "border": client.border,
+        "weight": client.weight,
+        "floating": client.floating,
+        "fullscreen": client.fullscreen,
+        "urgent": client.urgent,
+        "maximized_vert": client.maximized_vert,
+        "maximized_horz": client.maximized_horz,
+        "above": client.above,
+        "below": client.below,
+        "sticky": client.sticky,
+        "hidden": client.hidden,
+        "skip_taskbar": client.skip_taskbar,
+        "skip_pager": client.skip_pager,
+        "never_focus": client.never_focus,
+        "desktop": client.desktop,
+        "visible": client.visible,
+        "focused": focused,
+    })
+}
+
+/// Id of focused window
+fn focused_window(app: &Application) -> Option<u64> {
+    let rt = &app.runtime;
+    let workspace = rt
+        .screens
+        .get(rt.current_screen)?
+        .workspaces
+        .get(rt.current_workspace)?;
+    Some(workspace.clients.get(rt.current_client?)?.window_id)
+}
+
+/// Name of workspace as published in `_NET_DESKTOP_NAMES`
+pub fn workspace_name(app: &Application, screen: usize, workspace: usize) -> String {
+    match app
+        .config
+        .desktops
+        .names
+        .get(screen)
+        .and_then(|n| n.get(workspace))
+    {
+        Some(name) => name.to_string(),
+        None => format!("{}", workspace + 1),
+    }
+}
+
+pub fn focus_json(app: &Application) -> Value {
+    json!({
+        "screen": app.runtime.current_screen,
+        "workspace": app.runtime.current_workspace,
+        "client": app.runtime.current_client,
+        "window_id": focused_window(app),
+    })
+}
+
+pub fn screens_json(app: &Application) -> Value {
+    let focused = focused_window(app);
+    let screens: Vec<Value> = app
+        .runtime
+        .screens
+        .iter()
+        .enumerate()
+        .map(|(s, screen)| {
+            let workspaces: Vec<Value> = screen
+                .workspaces
+                .iter()
+                .enumerate()
+                .map(|(w, workspace)| {
+                    let clients: Vec<Value> = workspace
+                        .clients
+                        .iter()
+                        .map(|c| client_json(c, Some(c.window_id) == focused))
+                        .collect();
+                    json!({
+                        "index": w,
+                        "desktop": s * NUMBER_OF_DESKTOPS + w,
+                        "name": workspace_name(app, s, w),
+                        "layout": workspace.arrange.name(),
+                        "master_capacity": workspace.master_capacity,
+                        "master_width": workspace.master_width,
+                        "current_client": workspace.current_client,
+                        "clients": clients,
+                    })
+                })
+                .collect();
+            json!({
+                "index": s,
+                "number": screen.number,
+                "output": screen.output,
+                "x": screen.x,
+                "y": screen.y,
+                "width": screen.width,
+                "height": screen.height,
+                "bar_offsets": {
+                    "left": screen.bar_offsets.left,
+                    "up": screen.bar_offsets.up,
+                    "right": screen.bar_offsets.right,
+                    "down": screen.bar_offsets.down,
+                },
+                "current_workspace": screen.current_workspace,
+                "workspaces": workspaces,
+            })
+        })
+        .collect();
+    Value::Array(screens)
+}
+
+pub fn clients_json(app: &Application) -> Value {
+    let focused = focused_window(app);
+    let mut clients = vec![];
+    for (s, screen) in app.runtime.screens.iter().enumerate() {
+        for (w, workspace) in screen.workspaces.iter().enumerate() {
+            for client in &workspace.clients {
+                let mut c = client_json(client, Some(client.window_id) == focused);
+                c["screen"] = json!(s);
+                c["workspace"] = json!(w);
+                clients.push(c);
+            }
+        }
+    }
+    Value::Array(clients)
+}
+
+/// Full state of runtime
+pub fn state_json(app: &Application) -> Value {
+    json!({
+        "version": STATE_VERSION,
+        "focus": focus_json(app),
+        "screens": screens_json(app),
+    })
+}
+
+/// Answer `query` command: `state`, `screens`, `clients` or `focus`
+pub fn query(app: &Application, what: &str) -> Result<Value, String> {
+    match what {
+        "" | "state" => Ok(state_json(app)),
+        "screens" => Ok(screens_json(app)),
+        "clients" => Ok(clients_json(app)),
+        "focus" => Ok(focus_json(app)),
+        _ => Err(format!(
+            "unknown query `{what}`, expected state, screens, clients or focus"
+        )),
+    }
+}
+
+/// File `DumpInfo` writes state to, next to control socket
+pub fn dump_path() -> PathBuf {
+    socket_path().with_extension("json")
+}
+
+/// Write full state to [`dump_path`]
+pub fn dump_state(app: &Application) {
+    let path = dump_path();
+    let text = match serde_json::to_string_pretty(&state_json(app)) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Failed to serialize state: {e}");
+            return;
+        }
+    };
+    if let Err(e) = std::fs::write(&path, text + "\n") {
+        eprintln!("Failed to write {}: {e}", path.display());
+        return;
+    }
+    log!("|- State written to {}", path.display());
+}
@@ -2,7 +2,12 @@
 
 use std::ffi::CString;
 
-use crate::config::NUMBER_OF_DESKTOPS;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ipc::IpcServer;
+use crate::layout::LayoutRegistry;
+use crate::wrapper::xlib::FontSet;
 
 pub struct Application {
     pub config: Configuration,
@@ -13,23 +18,34 @@ pub struct Application {
 
 pub struct Configuration {
     pub key_actions: Vec<KeyAction>,
+    /// Gap between windows
     pub gap_width: usize,
+    /// Gap between windows & screen edge
+    pub outer_gap_width: usize,
+    /// No gaps when single tiled window is visible
+    pub smart_gaps: bool,
+    /// No border when single tiled window is visible
+    pub smart_borders: bool,
     pub border_size: usize,
     pub normal_border_color: Color,
     pub active_border_color: Color,
     pub urgent_border_color: Color,
+    pub three_column_master: MasterPosition,
+    /// Layouts switched by `NextLayout` & `PrevLayout`
+    pub layout_cycle: Vec<ArrangeEngine>,
+    pub tab_bar: TabBarConfig,
     pub desktops: DesktopsConfig,
     pub autostart: Vec<AutostartRuleCMD>,
     pub placements: Vec<PlacementRule>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct AutostartRuleCMD {
     pub cmd: Vec<CString>,
     pub rule: Option<(usize, usize)>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct PlacementRule {
     pub instance: Option<String>,
     pub class: Option<String>,
@@ -45,13 +61,15 @@ pub struct KeyAction {
     pub result: ActionResult,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub enum ActionResult {
     // Global management
     KillClient,
     Spawn(Vec<CString>),
     DumpInfo,
+    ReloadConfig,
     Quit,
+    Script(String),
     // Screen management
     MoveToScreen(ScreenSwitching),
     FocusOnScreen(ScreenSwitching),
@@ -59,9 +77,12 @@ pub enum ActionResult {
     MoveToWorkspace(u64),
     FocusOnWorkspace(u64),
     SetArrangeEngine(ArrangeEngine),
+    NextLayout, // Cycles through `layout_cycle`
+    PrevLayout,
     // Tiled engine 
     UpdateMasterCapacity(i64),
     UpdateMasterWidth(f64),
+    UpdateClientWeight(f64), // 0 resets weight
     RotateStack,     // Pops and pushes LAST window
     PopPushStack,    // Pops and pushes CURRENT window
     // Any Engine
@@ -69,13 +90,30 @@ pub enum ActionResult {
     CycleStack(i64), // Simply moves focus
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Tab strip of `Tabbed` layout, tabs are colored like borders
+#[derive(Clone)]
+pub struct TabBarConfig {
+    pub height: i32,
+    /// X font set pattern
+    pub font: String,
+    pub text_color: Color,
+}
+
+/// Where master column of `ThreeColumn` layout is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MasterPosition {
+    Left,
+    Center,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
 pub enum ScreenSwitching {
     Next,
     Previous,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
 pub struct Color {
     pub alpha: u8,
     pub red: u8,
@@ -84,17 +122,26 @@ pub struct Color {
 }
 
 pub struct DesktopsConfig {
-    pub keysyms: [u32; NUMBER_OF_DESKTOPS],
-    pub names: Vec<[String; NUMBER_OF_DESKTOPS]>,
-    pub splits: Vec<[f64; NUMBER_OF_DESKTOPS]>,
+    pub keysyms: Vec<u32>,
+    pub names: Vec<Vec<String>>,
+    pub splits: Vec<Vec<f64>>,
+    /// Initial layout of workspaces, [`ArrangeEngine::Tiled`] if not set
+    pub layouts: Vec<Vec<ArrangeEngine>>,
+    /// Initial master capacity of workspaces, 1 if not set
+    pub master_capacities: Vec<Vec<i64>>,
+    /// Gap of workspaces replacing `gap_width` & `outer_gap_width`, `None` keeps them
+    pub gaps: Vec<Vec<Option<usize>>>,
 }
 
 impl DesktopsConfig {
     pub fn new() -> DesktopsConfig {
         DesktopsConfig {
-            keysyms: [0; NUMBER_OF_DESKTOPS],
+            keysyms: vec![],
             names: vec![],
             splits: vec![],
+            layouts: vec![],
+            master_capacities: vec![],
+            gaps: vec![],
         }
     }
 }
@@ -118,17 +165,40 @@ pub struct Atoms {
     pub net_wm_name: u64,
     pub net_wm_state: u64,
     pub net_wm_state_demands_attention: u64,
+    pub net_wm_state_maximized_vert: u64,
+    pub net_wm_state_maximized_horz: u64,
+    pub net_wm_state_above: u64,
+    pub net_wm_state_below: u64,
+    pub net_wm_state_sticky: u64,
+    pub net_wm_state_hidden: u64,
+    pub net_wm_state_skip_taskbar: u64,
+    pub net_wm_state_skip_pager: u64,
     pub net_wm_check: u64,
     pub net_wm_fullscreen: u64,
     pub net_wm_window_type: u64,
     pub net_wm_window_type_dialog: u64,
     pub net_wm_window_type_dock: u64,
+    pub net_wm_window_type_desktop: u64,
+    pub net_wm_window_type_toolbar: u64,
+    pub net_wm_window_type_menu: u64,
+    pub net_wm_window_type_utility: u64,
+    pub net_wm_window_type_splash: u64,
+    pub net_wm_window_type_notification: u64,
+    pub net_wm_window_type_dropdown_menu: u64,
+    pub net_wm_window_type_popup_menu: u64,
+    pub net_wm_window_type_tooltip: u64,
+    pub net_wm_window_type_combo: u64,
+    pub net_wm_window_type_dnd: u64,
+    pub net_wm_strut: u64,
+    pub net_wm_strut_partial: u64,
     pub net_client_list: u64,
     pub net_number_of_desktops: u64,
     pub net_current_desktop: u64,
     pub net_wm_desktop: u64,
     pub net_desktop_names: u64,
     pub net_desktop_viewport: u64,
+    pub net_desktop_geometry: u64,
+    pub net_workarea: u64,
     pub net_wm_pid: u64,
 }
 
@@ -137,6 +207,7 @@ pub struct WmCore {
     pub root_win: u64,
     pub wm_check_win: u64,
     pub running: bool,
+    pub ipc: Option<IpcServer>,
 }
 
 impl std::fmt::Debug for WmCore {
@@ -146,6 +217,7 @@ impl std::fmt::Debug for WmCore {
             .field("root_win", &self.root_win)
             .field("wm_check_win", &self.wm_check_win)
             .field("running", &self.running)
+            .field("ipc", &self.ipc.as_ref().map(|ipc| &ipc.path))
             .finish()
     }
 }
@@ -159,6 +231,41 @@ pub struct Runtime {
     pub mouse_state: MouseState, // win, button, pos
     pub bars: Vec<Bar>, // Not in screens since logically bars are not limited to specific screen
     pub autostart_rules: Vec<AutostartRulePID>,
+    /// Workspaces of disconnected outputs
+    pub detached_screens: Vec<DetachedScreen>,
+    /// Built-in & user layouts
+    pub layouts: LayoutRegistry,
+    /// Tab strips of screens showing `Tabbed` workspace
+    pub tab_bars: Vec<TabBar>,
+    /// Font of tab strips, loaded with first strip
+    pub tab_font: Option<FontSet>,
+    /// Monitors changed, screens are updated once after event queue is drained
+    pub screens_changed: bool,
+}
+
+/// Window WM draws tabs in, see [`crate::tabs`]
+#[derive(Debug)]
+pub struct TabBar {
+    pub window_id: u64,
+    pub screen: usize,
+    /// Workspace tabs were drawn for
+    pub workspace: usize,
+    pub width: u32,
+    pub visible: bool,
+    /// Windows in tab order
+    pub tabs: Vec<u64>,
+}
+
+/// Workspaces of disconnected output, restored when output is connected again
+#[derive(Debug)]
+pub struct DetachedScreen {
+    /// Name of RandR output, e.g. `HDMI-1`
+    pub output: String,
+    pub current_workspace: usize,
+    /// Workspace settings, their clients are shown on remaining screen meanwhile
+    pub workspaces: Vec<Workspace>,
+    /// Windows of every workspace in stack order
+    pub windows: Vec<Vec<u64>>,
 }
 
 #[derive(Debug)]
@@ -171,6 +278,8 @@ pub struct AutostartRulePID {
 #[derive(Debug)]
 pub struct Screen {
     pub number: i64,
+    /// Name of RandR output, e.g. `HDMI-1`
+    pub output: String,
     pub x: i64,
     pub y: i64,
     pub width: i64,
@@ -180,10 +289,19 @@ pub struct Screen {
     pub bar_offsets: BarOffsets,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum ArrangeEngine {
     Tiled,
-    Mono    
+    Mono,
+    Grid,
+    CenteredMaster,
+    BottomStack,
+    Spiral,
+    Dwindle,
+    Tabbed,
+    ThreeColumn,
+    /// Layout registered in `config::layouts()` under given name
+    Custom(String),
 }
 
 #[derive(Debug)]
@@ -194,6 +312,8 @@ pub struct Workspace {
     pub master_capacity: i64,
     pub master_width: f64,
     pub arrange: ArrangeEngine,
+    /// Overrides gaps from config
+    pub gap: Option<usize>,
 }
 
 #[derive(Debug, Default)]
@@ -211,12 +331,36 @@ pub struct Client {
     pub ox: i32,
     pub oy: i32,
     pub border: u32,
+    /// Share of column height relative to other clients in layouts that support it, 1.0 by default
+    pub weight: f64,
     // Flags
     pub visible: bool,
     pub floating: bool,
     pub fullscreen: bool,
     pub fixed: bool,
     pub urgent: bool,
+    /// Covers usable area of screen vertically, not tiled
+    pub maximized_vert: bool,
+    /// Covers usable area of screen horizontally, not tiled
+    pub maximized_horz: bool,
+    /// `y` & `h` before maximizing vertically, restored when it is removed
+    pub unmaximized_vert: Option<(i32, u32)>,
+    /// `x` & `w` before maximizing horizontally, restored when it is removed
+    pub unmaximized_horz: Option<(i32, u32)>,
+    /// Kept above other windows
+    pub above: bool,
+    /// Kept below other windows
+    pub below: bool,
+    /// Follows its screen to every workspace
+    pub sticky: bool,
+    /// Minimized, not shown until activated
+    pub hidden: bool,
+    pub skip_taskbar: bool,
+    pub skip_pager: bool,
+    /// Notifications, menus & desktop windows are never focused by WM
+    pub never_focus: bool,
+    /// Desktop window, e.g. `pcmanfm --desktop`, not listed in `_NET_CLIENT_LIST`
+    pub desktop: bool,
     // Restrictions
     pub minw: i32,
     pub minh: i32,
@@ -246,4 +390,81 @@ pub struct Bar {
     pub y: i64,
     pub w: usize,
     pub h: usize,
+    /// Reserved space, offsets are guessed from geometry if dock sets none
+    pub strut: Option<Strut>,
+}
+
+/// Space reserved by dock at edges of root window, see `_NET_WM_STRUT_PARTIAL`
+///
+/// Ranges are in root coordinates, inclusive. `_NET_WM_STRUT` reserves whole edges.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Strut {
+    pub left: i64,
+    pub right: i64,
+    pub top: i64,
+    pub bottom: i64,
+    pub left_start_y: i64,
+    pub left_end_y: i64,
+    pub right_start_y: i64,
+    pub right_end_y: i64,
+    pub top_start_x: i64,
+    pub top_end_x: i64,
+    pub bottom_start_x: i64,
+    pub bottom_end_x: i64,
+}
+
+/// Changes of WM state reported to socket subscribers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum WmEvent {
+    WorkspaceFocused {
+        screen: usize,
+        workspace: usize,
+    },
+    WindowOpened {
+        window_id: u64,
+        name: String,
+        screen: usize,
+        workspace: usize,
+    },
+    WindowClosed {
+        window_id: u64,
+        screen: usize,
+        workspace: usize,
+    },
+    WindowFocused {
+        window_id: u64,
+        name: String,
+        screen: usize,
+        workspace: usize,
+    },
+    WindowTitleChanged {
+        window_id: u64,
+        name: String,
+        screen: usize,
+        workspace: usize,
+    },
+    UrgencyChanged {
+        window_id: u64,
+        urgent: bool,
+        screen: usize,
+        workspace: usize,
+    },
+    LayoutChanged {
+        #[serde(serialize_with = "serialize_layout_name")]
+        layout: ArrangeEngine,
+        screen: usize,
+        workspace: usize,
+    },
+    ScreensChanged {
+        screens: usize,
+    },
+}
+
+/// Layouts are sent by registry name, same as accepted by `rtwmc layout`
+fn serialize_layout_name<S: serde::Serializer>(
+    ae: &ArrangeEngine,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(ae.name())
 }
@@ -0,0 +1,302 @@
+//! Tab strip of `Tabbed` layout
+//!
+//! Every screen gets own override-redirect window, created first time `Tabbed` workspace is
+//! shown on it. Strip has one tab per tiled client with its `window_name`, focused one is
+//! drawn in active border color, urgent ones in urgent border color. Clicking tab focuses its
+//! client. Strip is hidden while workspace has single client or fullscreen one.
+
+use x11::xlib::ButtonPressMask;
+use x11::xlib::CWEventMask;
+use x11::xlib::CWOverrideRedirect;
+use x11::xlib::ExposureMask;
+use x11::xlib::XExposeEvent;
+use x11::xlib::XSetWindowAttributes;
+
+use crate::arrange::is_tiled;
+use crate::helper::*;
+use crate::logic::*;
+use crate::structs::*;
+use crate::utils::*;
+use crate::wrapper::xlib::*;
+
+/// Space between tab edge and its title
+const TAB_PADDING: i32 = 4;
+
+/// Windows to show tabs for, `None` if workspace does not show strip
+fn workspace_tabs(workspace: &Workspace) -> Option<Vec<u64>> {
+    if !matches!(workspace.arrange, ArrangeEngine::Tabbed)
+        || workspace.clients.iter().any(|c| c.fullscreen)
+    {
+        return None;
+    }
+    let tabs: Vec<u64> = workspace
+        .clients
+        .iter()
+        .filter(|c| is_tiled(c))
+        .map(|c| c.window_id)
+        .collect();
+    // Single client is shown in mono
+    (tabs.len() > 1).then_some(tabs)
+}
+
+/// Create strip window for screen, returns its index in `tab_bars`
+fn create_tab_bar(app: &mut Application, screen: usize) -> usize {
+    let win = create_simple_window(
+        app.core.display,
+        app.core.root_win,
+        0,
+        0,
+        1,
+        1,
+        0,
+        0,
+        argb_to_int(app.config.normal_border_color),
+    );
+    let mut wa: XSetWindowAttributes = XSetWindowAttributes {
+        background_pixmap: 0,
+        background_pixel: 0,
+        border_pixmap: 0,
+        border_pixel: 0,
+        bit_gravity: 0,
+        win_gravity: 0,
+        backing_store: 0,
+        backing_planes: 0,
+        backing_pixel: 0,
+        save_under: 0,
+        event_mask: ButtonPressMask | ExposureMask,
+        do_not_propagate_mask: 0,
+        override_redirect: 1,
+        colormap: 0,
+        cursor: 0,
+    };
+    change_window_attributes(
+        app.core.display,
+        win,
+        CWOverrideRedirect | CWEventMask,
+        &mut wa,
+    );
+
+    if app.runtime.tab_font.is_none() {
+        app.runtime.tab_font = create_font_set(app.core.display, &app.config.tab_bar.font);
+        if app.runtime.tab_font.is_none() {
+            eprintln!(
+                "Failed to load tab font `{}`, tabs are drawn without titles",
+                app.config.tab_bar.font
+            );
+        }
+    }
+
+    app.runtime.tab_bars.push(TabBar {
+        window_id: win,
+        screen,
+        workspace: 0,
+        width: 0,
+        visible: false,
+        tabs: vec![],
+    });
+    app.runtime.tab_bars.len() - 1
+}
+
+/// Show strip of current workspace of screen or hide it if workspace is not tabbed
+///
+/// 1. Collect tabs
+/// 2. Create window if screen has none
+/// 3. Place, raise & draw
+pub fn update_tab_bar(app: &mut Application, screen: usize) {
+    // 1. Collect
+    let s = &app.runtime.screens[screen];
+    let workspace = s.current_workspace;
+    let ba = s.bar_offsets;
+    let (x, y, width) = (
+        s.x as i32 + ba.left as i32,
+        s.y as i32 + ba.up as i32,
+        (s.width - (ba.left + ba.right) as i64).max(1) as u32,
+    );
+    let index = app.runtime.tab_bars.iter().position(|b| b.screen == screen);
+    let tabs = match (workspace_tabs(&s.workspaces[workspace]), index) {
+        (Some(tabs), _) => tabs,
+        (None, Some(index)) => {
+            hide_tab_bar(app, index);
+            return;
+        }
+        (None, None) => return,
+    };
+
+    // 2. Create
+    let index = match index {
+        Some(index) => index,
+        None => create_tab_bar(app, screen),
+    };
+
+    // 3. Place & draw
+    let bar = &mut app.runtime.tab_bars[index];
+    bar.workspace = workspace;
+    bar.width = width;
+    bar.tabs = tabs;
+    move_resize_window(
+        app.core.display,
+        bar.window_id,
+        x,
+        y,
+        width,
+        app.config.tab_bar.height as u32,
+    );
+    if !bar.visible {
+        map_window(app.core.display, bar.window_id);
+        bar.visible = true;
+    }
+    raise_window(app.core.display, bar.window_id);
+    draw_tab_bar(app, index);
+}
+
+/// Update strips of all screens, dropping ones of removed screens
+pub fn update_tab_bars(app: &mut Application) {
+    let screens = app.runtime.screens.len();
+    for bar in app.runtime.tab_bars.iter().filter(|b| b.screen >= screens) {
+        destroy_window(app.core.display, bar.window_id);
+    }
+    app.runtime.tab_bars.retain(|b| b.screen < screens);
+    for screen in 0..screens {
+        update_tab_bar(app, screen);
+    }
+}
+
+fn hide_tab_bar(app: &mut Application, index: usize) {
+    let bar = &mut app.runtime.tab_bars[index];
+    if bar.visible {
+        unmap_window(app.core.display, bar.window_id);
+        bar.visible = false;
+    }
+}
+
+/// Hide strip if it shows tabs of given workspace
+pub fn hide_workspace_tab_bar(app: &mut Application, screen: usize, workspace: usize) {
+    if let Some(index) = app
+        .runtime
+        .tab_bars
+        .iter()
+        .position(|b| b.screen == screen && b.workspace == workspace)
+    {
+        hide_tab_bar(app, index);
+    }
+}
+
+/// Fill tabs with colors & titles, last tab takes what is left after rounding
+fn draw_tab_bar(app: &mut Application, index: usize) {
+    let bar = &app.runtime.tab_bars[index];
+    if !bar.visible || bar.tabs.is_empty() {
+        return;
+    }
+    // Screen may be already removed
+    let workspace = match app.runtime.screens.get(bar.screen) {
+        Some(screen) => &screen.workspaces[bar.workspace],
+        None => return,
+    };
+    let focused = workspace
+        .current_client
+        .and_then(|c| workspace.clients.get(c))
+        .map(|c| c.window_id);
+
+    let gc = create_gc(app.core.display, bar.window_id);
+    let count = bar.tabs.len() as i32;
+    let tab_width = bar.width as i32 / count;
+    for (i, &win) in bar.tabs.iter().enumerate() {
+        let client = workspace.clients.iter().find(|c| c.window_id == win);
+        let (name, urgent) = match client {
+            Some(c) => (c.window_name.as_str(), c.urgent),
+            None => ("", false),
+        };
+        let color = if Some(win) == focused {
+            app.config.active_border_color
+        } else if urgent {
+            app.config.urgent_border_color
+        } else {
+            app.config.normal_border_color
+        };
+        let x = tab_width * i as i32;
+        let w = if i as i32 == count - 1 {
+            bar.width as i32 - x
+        } else {
+            tab_width
+        };
+
+        set_foreground(app.core.display, gc, argb_to_int(color));
+        fill_rectangle(
+            app.core.display,
+            bar.window_id,
+            gc,
+            x,
+            0,
+            w as u32,
+            app.config.tab_bar.height as u32,
+        );
+        set_foreground(
+            app.core.display,
+            gc,
+            argb_to_int(app.config.tab_bar.text_color),
+        );
+        // Separator, titles spilling over are covered by next tab
+        if i != 0 {
+            fill_rectangle(
+                app.core.display,
+                bar.window_id,
+                gc,
+                x,
+                0,
+                1,
+                app.config.tab_bar.height as u32,
+            );
+        }
+        if let Some(font) = &app.runtime.tab_font {
+            let y = (app.config.tab_bar.height - font.height) / 2 + font.ascent;
+            draw_string(
+                app.core.display,
+                bar.window_id,
+                font,
+                gc,
+                x + TAB_PADDING,
+                y,
+                name,
+            );
+        }
+    }
+    free_gc(app.core.display, gc);
+}
+
+/// Redraw strip when it is exposed
+pub fn tab_bar_expose(app: &mut Application, expose: XExposeEvent) {
+    if expose.count != 0 {
+        return;
+    }
+    if let Some(index) = app
+        .runtime
+        .tab_bars
+        .iter()
+        .position(|b| b.window_id == expose.window)
+    {
+        draw_tab_bar(app, index);
+    }
+}
+
+/// Focus client of clicked tab. Returns `false` if window is not a strip
+pub fn tab_bar_click(app: &mut Application, win: u64, x: i32) -> bool {
+    let bar = match app.runtime.tab_bars.iter().find(|b| b.window_id == win) {
+        Some(bar) => bar,
+        None => return false,
+    };
+    if bar.tabs.is_empty() {
+        return true;
+    }
+    let tab_width = (bar.width as i32 / bar.tabs.len() as i32).max(1);
+    let index = ((x.max(0) / tab_width) as usize).min(bar.tabs.len() - 1);
+    let (target, screen) = (bar.tabs[index], bar.screen);
+    log!("|- Clicked tab {index} of screen {screen}");
+
+    if let Some(cw) = get_current_client_id(app) {
+        unfocus(app, cw);
+    }
+    focus(app, target);
+    raise_window(app.core.display, target);
+    update_tab_bar(app, screen);
+    true
+}
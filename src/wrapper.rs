@@ -1,6 +1,23 @@
 //! \*Safe\* wrap for x11
 
 pub mod sys {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicI32;
+    use std::sync::atomic::Ordering;
+
+    static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+    /// Self-pipe woken by SIGHUP, so signal arriving right before `poll` is not missed
+    static RELOAD_PIPE: [AtomicI32; 2] = [AtomicI32::new(-1), AtomicI32::new(-1)];
+
+    extern "C" fn sighup_handler(_signal: i32) {
+        RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+        let fd = RELOAD_PIPE[1].load(Ordering::SeqCst);
+        if fd >= 0 {
+            // `write` is async-signal-safe, full pipe already wakes reader
+            unsafe { libc::write(fd, [1u8].as_ptr() as *const libc::c_void, 1) };
+        }
+    }
+
     pub fn set_locale(c: i32, l: &str) {
         unsafe {
             let locale = std::ffi::CString::new(l).unwrap();
@@ -20,6 +37,59 @@ pub mod sys {
             let _ = sigaction(SIGCHLD, &sa);
         }
     }
+
+    /// Request config reload on SIGHUP. Handler writes to pipe returned by [`reload_fd`]
+    /// so it wakes [`wait_fds`] even if signal came before it started waiting
+    pub fn reload_on_sighup() {
+        use nix::sys::signal::*;
+        let mut fds = [-1; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } == 0 {
+            RELOAD_PIPE[0].store(fds[0], Ordering::SeqCst);
+            RELOAD_PIPE[1].store(fds[1], Ordering::SeqCst);
+        } else {
+            eprintln!("Failed to create SIGHUP pipe, reload may wait for next event");
+        }
+        unsafe {
+            let sa = SigAction::new(
+                SigHandler::Handler(sighup_handler),
+                SaFlags::empty(),
+                SigSet::empty(),
+            );
+            let _ = sigaction(SIGHUP, &sa);
+        }
+    }
+
+    /// Read end of SIGHUP pipe to wait on
+    pub fn reload_fd() -> Option<i32> {
+        let fd = RELOAD_PIPE[0].load(Ordering::SeqCst);
+        (fd >= 0).then_some(fd)
+    }
+
+    /// Returns true once for every received SIGHUP
+    pub fn take_reload_request() -> bool {
+        if let Some(fd) = reload_fd() {
+            // Drain wake-up bytes, flag below tells if reload is needed
+            let mut buf = [0u8; 64];
+            while unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) } > 0 {}
+        }
+        RELOAD_REQUESTED.swap(false, Ordering::SeqCst)
+    }
+
+    /// Block until any of `readable` has data to read, any of `writable` can take more data
+    /// or signal arrives
+    pub fn wait_fds(readable: &[i32], writable: &[i32]) {
+        use nix::poll::*;
+        let mut fds: Vec<PollFd> = readable
+            .iter()
+            .map(|fd| PollFd::new(*fd, PollFlags::POLLIN))
+            .chain(
+                writable
+                    .iter()
+                    .map(|fd| PollFd::new(*fd, PollFlags::POLLOUT)),
+            )
+            .collect();
+        let _ = poll(&mut fds, -1);
+    }
 }
 
 pub mod xlib {
@@ -82,6 +152,7 @@ pub mod xlib {
         }
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn open_display(display_name: Option<&str>) -> Option<&mut x11::xlib::Display> {
         unsafe {
             let result = match display_name {
@@ -115,6 +186,17 @@ pub mod xlib {
         unsafe { x11::xlib::XDefaultScreen(display as *mut x11::xlib::Display) }
     }
 
+    /// Size of default screen in pixels
+    pub fn display_size(display: &mut x11::xlib::Display) -> (i32, i32) {
+        unsafe {
+            let screen = x11::xlib::XDefaultScreen(display as *mut x11::xlib::Display);
+            (
+                x11::xlib::XDisplayWidth(display as *mut x11::xlib::Display, screen),
+                x11::xlib::XDisplayHeight(display as *mut x11::xlib::Display, screen),
+            )
+        }
+    }
+
     pub fn grab_key(dpy: &mut x11::xlib::Display, keysym: u32, mask: u32) {
         unsafe {
             x11::xlib::XGrabKey(
@@ -129,6 +211,12 @@ pub mod xlib {
         }
     }
 
+    pub fn ungrab_key(dpy: &mut x11::xlib::Display, keycode: i32, mask: u32) {
+        unsafe {
+            x11::xlib::XUngrabKey(dpy, keycode, mask, x11::xlib::XDefaultRootWindow(dpy));
+        }
+    }
+
     pub fn grab_button(dpy: &mut x11::xlib::Display, win: u64, button: u32, mask: u32) {
         unsafe {
             x11::xlib::XGrabButton(
@@ -472,7 +560,13 @@ pub mod xlib {
                     xe.type_ = ConfigureRequest;
                     xe.configure_request = configure_request_event
                 }
-                EEvent::Unmanaged { .. } => {}
+                EEvent::Expose { expose } => {
+                    xe.type_ = x11::xlib::Expose;
+                    xe.expose = expose
+                }
+                EEvent::ScreenChangeNotify
+                | EEvent::OutputChangeNotify
+                | EEvent::Unmanaged { .. } => {}
             };
 
             x11::xlib::XSendEvent(
@@ -503,6 +597,19 @@ pub mod xlib {
         unsafe {
             let mut ev: XEvent = XEvent { type_: 0 };
             x11::xlib::XNextEvent(display as *mut x11::xlib::Display, &mut ev as *mut XEvent);
+            // RandR events have dynamic types, check them first
+            if let Some(base) = crate::wrapper::xrandr::xrandr_event_base() {
+                if ev.type_ == base + x11::xrandr::RRScreenChangeNotify {
+                    x11::xrandr::XRRUpdateConfiguration(&mut ev as *mut XEvent);
+                    return EEvent::ScreenChangeNotify;
+                }
+                if ev.type_ == base + x11::xrandr::RRNotify {
+                    let notify = &*(&ev as *const XEvent as *const x11::xrandr::XRRNotifyEvent);
+                    if notify.subtype == x11::xrandr::RRNotify_OutputChange {
+                        return EEvent::OutputChangeNotify;
+                    }
+                }
+            }
             match ev.type_ {
                 x11::xlib::KeyPress => EEvent::KeyPress { key: ev.key },
                 x11::xlib::KeyRelease => EEvent::KeyRelease { key: ev.key },
@@ -543,9 +650,13 @@ pub mod xlib {
                 x11::xlib::ConfigureRequest => EEvent::ConfigureRequest {
                     configure_request_event: ev.configure_request,
                 },
+                x11::xlib::Expose => EEvent::Expose { expose: ev.expose },
                 _ => EEvent::Unmanaged {
                     type_: ev.type_,
-                    name: EVENT_LOOKUP[ev.type_ as usize],
+                    name: EVENT_LOOKUP
+                        .get(ev.type_ as usize)
+                        .copied()
+                        .unwrap_or("ExtensionEvent"),
                 },
             }
         }
@@ -617,6 +728,12 @@ pub mod xlib {
         }
     }
 
+    pub fn lower_window(display: &mut x11::xlib::Display, w: u64) {
+        unsafe {
+            x11::xlib::XLowerWindow(display as *mut x11::xlib::Display, w);
+        }
+    }
+
     #[allow(dead_code)]
     pub fn x_kill_client(display: &mut x11::xlib::Display, w: u64) {
         unsafe {
@@ -635,19 +752,172 @@ pub mod xlib {
         }
     }
 
+    pub fn pending(display: &mut x11::xlib::Display) -> i32 {
+        unsafe { x11::xlib::XPending(display as *mut x11::xlib::Display) }
+    }
+
+    pub fn connection_number(display: &mut x11::xlib::Display) -> i32 {
+        unsafe { x11::xlib::XConnectionNumber(display as *mut x11::xlib::Display) }
+    }
+
     pub fn map_window(display: &mut x11::xlib::Display, w: u64) {
         unsafe {
             x11::xlib::XMapWindow(display as *mut x11::xlib::Display, w);
         }
     }
 
+    pub fn unmap_window(display: &mut x11::xlib::Display, w: u64) {
+        unsafe {
+            x11::xlib::XUnmapWindow(display as *mut x11::xlib::Display, w);
+        }
+    }
+
+    pub fn destroy_window(display: &mut x11::xlib::Display, w: u64) {
+        unsafe {
+            x11::xlib::XDestroyWindow(display as *mut x11::xlib::Display, w);
+        }
+    }
+
+    pub fn create_gc(display: &mut x11::xlib::Display, w: u64) -> x11::xlib::GC {
+        unsafe {
+            x11::xlib::XCreateGC(
+                display as *mut x11::xlib::Display,
+                w,
+                0,
+                std::ptr::null_mut(),
+            )
+        }
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn free_gc(display: &mut x11::xlib::Display, gc: x11::xlib::GC) {
+        unsafe {
+            x11::xlib::XFreeGC(display as *mut x11::xlib::Display, gc);
+        }
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn set_foreground(display: &mut x11::xlib::Display, gc: x11::xlib::GC, pixel: u64) {
+        unsafe {
+            x11::xlib::XSetForeground(display as *mut x11::xlib::Display, gc, pixel);
+        }
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_rectangle(
+        display: &mut x11::xlib::Display,
+        w: u64,
+        gc: x11::xlib::GC,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) {
+        unsafe {
+            x11::xlib::XFillRectangle(
+                display as *mut x11::xlib::Display,
+                w,
+                gc,
+                x,
+                y,
+                width,
+                height,
+            );
+        }
+    }
+
+    /// Load font set for drawing UTF-8 text, `None` if no font matches pattern
+    pub fn create_font_set(display: &mut x11::xlib::Display, pattern: &str) -> Option<FontSet> {
+        let pattern = std::ffi::CString::new(pattern).ok()?;
+        unsafe {
+            let mut missing: *mut *mut i8 = std::ptr::null_mut();
+            let mut missing_count = 0;
+            let mut default_string: *mut i8 = std::ptr::null_mut();
+            let set = x11::xlib::XCreateFontSet(
+                display as *mut x11::xlib::Display,
+                pattern.as_ptr(),
+                &mut missing as *mut *mut *mut i8,
+                &mut missing_count as *mut i32,
+                &mut default_string as *mut *mut i8,
+            );
+            if !missing.is_null() {
+                x11::xlib::XFreeStringList(missing);
+            }
+            if set.is_null() {
+                return None;
+            }
+            let extents = &*x11::xlib::XExtentsOfFontSet(set);
+            Some(FontSet {
+                set,
+                ascent: -extents.max_logical_extent.y as i32,
+                height: extents.max_logical_extent.height as i32,
+            })
+        }
+    }
+
+    pub fn free_font_set(display: &mut x11::xlib::Display, font: FontSet) {
+        unsafe {
+            x11::xlib::XFreeFontSet(display as *mut x11::xlib::Display, font.set);
+        }
+    }
+
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_string(
+        display: &mut x11::xlib::Display,
+        w: u64,
+        font: &FontSet,
+        gc: x11::xlib::GC,
+        x: i32,
+        y: i32,
+        text: &str,
+    ) {
+        unsafe {
+            x11::xlib::Xutf8DrawString(
+                display as *mut x11::xlib::Display,
+                w,
+                font.set,
+                gc,
+                x,
+                y,
+                text.as_ptr() as *const i8,
+                text.len() as i32,
+            );
+        }
+    }
+
     pub fn keysym_to_keycode(display: &mut x11::xlib::Display, keysym: u32) -> u32 {
         unsafe {
             x11::xlib::XKeysymToKeycode(display as *mut x11::xlib::Display, keysym as u64) as u32
         }
     }
 
+    /// Returns `NoSymbol` (0) if name is not known keysym
+    pub fn string_to_keysym(name: &str) -> u32 {
+        match std::ffi::CString::new(name) {
+            Ok(c) => unsafe { x11::xlib::XStringToKeysym(c.as_ptr()) as u32 },
+            Err(_) => 0,
+        }
+    }
+
+    pub fn keysym_to_string(keysym: u32) -> Option<String> {
+        unsafe {
+            let name = x11::xlib::XKeysymToString(keysym as u64);
+            if name.is_null() {
+                None
+            } else {
+                Some(
+                    std::ffi::CStr::from_ptr(name)
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     pub fn change_property(
         display: &mut x11::xlib::Display,
         w: u64,
@@ -849,12 +1119,25 @@ pub mod xlib {
         ConfigureRequest {
             configure_request_event: x11::xlib::XConfigureRequestEvent,
         },
+        Expose {
+            expose: x11::xlib::XExposeEvent,
+        },
+        ScreenChangeNotify,
+        OutputChangeNotify,
         Unmanaged {
             type_: i32,
             name: &'static str,
         },
     }
 
+    /// Font set loaded by [`create_font_set`], `ascent` & `height` in pixels
+    #[derive(Debug)]
+    pub struct FontSet {
+        pub set: x11::xlib::XFontSet,
+        pub ascent: i32,
+        pub height: i32,
+    }
+
     #[derive(Default)]
     pub struct ClassHint {
         pub res_name: Option<String>,
@@ -862,6 +1145,94 @@ pub mod xlib {
     }
 }
 
+pub mod xrandr {
+    use std::sync::atomic::AtomicI32;
+    use std::sync::atomic::Ordering;
+
+    /// Event base of RandR extension, -1 until [`xrandr_init`] succeeds
+    static EVENT_BASE: AtomicI32 = AtomicI32::new(-1);
+
+    /// Monitor as reported by RandR (or Xinerama)
+    #[derive(Debug, Clone)]
+    pub struct MonitorInfo {
+        pub name: String,
+        pub primary: bool,
+        pub x: i32,
+        pub y: i32,
+        pub width: i32,
+        pub height: i32,
+    }
+
+    /// Check for RandR 1.5 and subscribe to screen & output changes on root window
+    pub fn xrandr_init(display: &mut x11::xlib::Display, root: u64) -> bool {
+        unsafe {
+            let dpy = display as *mut x11::xlib::Display;
+            let (mut event_base, mut error_base) = (0, 0);
+            if x11::xrandr::XRRQueryExtension(dpy, &mut event_base, &mut error_base) == 0 {
+                return false;
+            }
+            let (mut major, mut minor) = (0, 0);
+            if x11::xrandr::XRRQueryVersion(dpy, &mut major, &mut minor) == 0
+                || (major, minor) < (1, 5)
+            {
+                return false;
+            }
+            x11::xrandr::XRRSelectInput(
+                dpy,
+                root,
+                x11::xrandr::RRScreenChangeNotifyMask | x11::xrandr::RROutputChangeNotifyMask,
+            );
+            EVENT_BASE.store(event_base, Ordering::SeqCst);
+            true
+        }
+    }
+
+    pub fn xrandr_event_base() -> Option<i32> {
+        match EVENT_BASE.load(Ordering::SeqCst) {
+            -1 => None,
+            base => Some(base),
+        }
+    }
+
+    /// Active monitors, `None` if RandR is not available or has no monitors
+    pub fn xrandr_get_monitors(
+        display: &mut x11::xlib::Display,
+        root: u64,
+    ) -> Option<Vec<MonitorInfo>> {
+        xrandr_event_base()?;
+        unsafe {
+            let mut amount: i32 = 0;
+            let monitors = x11::xrandr::XRRGetMonitors(
+                display as *mut x11::xlib::Display,
+                root,
+                1,
+                &mut amount as *mut i32,
+            );
+            if monitors.is_null() {
+                return None;
+            }
+            let result: Vec<MonitorInfo> =
+                std::slice::from_raw_parts(monitors, amount.max(0) as usize)
+                    .iter()
+                    .map(|m| MonitorInfo {
+                        name: super::xlib::get_atom_name(display, m.name),
+                        primary: m.primary != 0,
+                        x: m.x,
+                        y: m.y,
+                        width: m.width,
+                        height: m.height,
+                    })
+                    .collect();
+            x11::xrandr::XRRFreeMonitors(monitors);
+            if result.is_empty() {
+                None
+            } else {
+                Some(result)
+            }
+        }
+    }
+}
+
 pub mod xinerama {
     pub fn xinerama_query_screens(
         display: &mut x11::xlib::Display,